@@ -0,0 +1,46 @@
+//! Typechecking and normalizing long let-chains. Run with
+//! `cargo bench --bench let_chain`. To compare two versions, pass
+//! `-- --save-baseline <name>` when running the first one, and
+//! `-- --baseline <name>` when running the second.
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// `let x0 = 0 in let x1 = x0 + 1 in ... in x{n-1}`
+fn distinct_let_chain(n: usize) -> String {
+    let mut s = String::from("let x0 = 0\nin ");
+    for i in 1..n {
+        s.push_str(&format!("let x{} : Natural = x{} + 1\nin ", i, i - 1));
+    }
+    s.push_str(&format!("x{}", n - 1));
+    s
+}
+
+/// `let x = 0 in let x = x + 1 in ... in x`, where every binder shadows the
+/// previous one.
+fn shadowing_let_chain(n: usize) -> String {
+    let mut s = String::from("let x = 0\nin ");
+    for _ in 1..n {
+        s.push_str("let x : Natural = x + 1\nin ");
+    }
+    s.push_str("x");
+    s
+}
+
+fn bench_let_chain(c: &mut Criterion, name: &str, expr: String) {
+    c.bench_function(name, move |b| {
+        b.iter(|| dhall::de::from_str::<u64>(&expr, None).unwrap())
+    });
+}
+
+fn let_chains(c: &mut Criterion) {
+    for &n in &[100, 1000, 3000] {
+        let name = format!("let_chain_distinct_{}", n);
+        bench_let_chain(c, &name, distinct_let_chain(n));
+    }
+    for &n in &[1000, 3000] {
+        let name = format!("let_chain_shadowing_{}", n);
+        bench_let_chain(c, &name, shadowing_let_chain(n));
+    }
+}
+
+criterion_group!(benches, let_chains);
+criterion_main!(benches);
@@ -0,0 +1,90 @@
+//! Per-phase benchmarks. Run with `cargo bench --bench phases`.
+use std::path::{Path, PathBuf};
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use dhall::pipeline::Parsed;
+
+/// `{ f0 = 0, f1 = "1", f2 = [2], f3 = 3, ... }`, typed.
+fn large_record(n: usize) -> String {
+    let fields: Vec<_> = (0..n)
+        .map(|i| match i % 3 {
+            0 => format!("f{} = {}", i, i),
+            1 => format!("f{} = \"{}\"", i, i),
+            _ => format!("f{} = [{}]", i, i),
+        })
+        .collect();
+    format!("{{ {} }}", fields.join(", "))
+}
+
+/// `let x0 = 0 let x1 = x0 + 1 ... in x{n-1}`
+fn let_chain(n: usize) -> String {
+    let mut s = String::from("let x0 = 0\n");
+    for i in 1..n {
+        s.push_str(&format!("let x{} = x{} + 1\n", i, i - 1));
+    }
+    s.push_str(&format!("in x{}", n - 1));
+    s
+}
+
+fn prelude() -> Option<PathBuf> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../dhall-lang/Prelude/package.dhall");
+    if path.exists() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Where to parse an input from
+#[derive(Clone)]
+enum Source {
+    Str(String),
+    File(PathBuf),
+}
+
+impl Source {
+    fn parse(&self) -> Parsed {
+        match self {
+            Source::Str(s) => Parsed::parse_str(s).unwrap(),
+            Source::File(f) => Parsed::parse_file(f).unwrap(),
+        }
+    }
+}
+
+fn bench_phases(c: &mut Criterion, name: &str, src: Source) {
+    let s = src.clone();
+    c.bench_function(&format!("{}/parse", name), move |b| b.iter(|| s.parse()));
+    let s = src.clone();
+    c.bench_function(&format!("{}/resolve", name), move |b| {
+        b.iter_with_setup(|| s.parse(), |p| p.resolve().unwrap())
+    });
+    let s = src.clone();
+    c.bench_function(&format!("{}/typecheck", name), move |b| {
+        b.iter_with_setup(
+            || s.parse().resolve().unwrap(),
+            |r| r.typecheck().unwrap(),
+        )
+    });
+    let s = src;
+    c.bench_function(&format!("{}/normalize", name), move |b| {
+        b.iter_with_setup(
+            || s.parse().resolve().unwrap().typecheck().unwrap(),
+            |t| t.normalize(),
+        )
+    });
+}
+
+fn generated(c: &mut Criterion) {
+    bench_phases(c, "large_record", Source::Str(large_record(1000)));
+    bench_phases(c, "let_chain", Source::Str(let_chain(1000)));
+}
+
+fn prelude_package(c: &mut Criterion) {
+    if let Some(path) = prelude() {
+        bench_phases(c, "prelude", Source::File(path));
+    }
+}
+
+criterion_group!(benches, generated, prelude_package);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Record-heavy normalization, which label interning speeds up. Run with
+//! `cargo bench --bench records`.
+use std::collections::HashMap;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+/// `{ f0 = 0, f1 = 1, ... }`
+fn record(n: usize) -> String {
+    let fields: Vec<_> = (0..n).map(|i| format!("f{} = {}", i, i)).collect();
+    format!("{{ {} }}", fields.join(", "))
+}
+
+/// Adds up every field of a record with `n` fields, once per field.
+fn field_accesses(n: usize) -> String {
+    let accesses: Vec<_> = (0..n).map(|i| format!("r.f{}", i)).collect();
+    format!("let r = {}\nin {}", record(n), accesses.join(" + "))
+}
+
+/// Merges `n` single-field records into one.
+fn record_merges(n: usize) -> String {
+    let records: Vec<_> =
+        (0..n).map(|i| format!("{{ f{} = {} }}", i, i)).collect();
+    records.join(" ∧ ")
+}
+
+fn records(c: &mut Criterion) {
+    for &n in &[100, 1000] {
+        let expr = field_accesses(n);
+        c.bench_function(&format!("record_field_access_{}", n), move |b| {
+            b.iter(|| dhall::de::from_str::<u64>(&expr, None).unwrap())
+        });
+    }
+    for &n in &[100, 500] {
+        let expr = record_merges(n);
+        c.bench_function(&format!("record_merge_{}", n), move |b| {
+            b.iter(|| {
+                dhall::de::from_str::<HashMap<String, u64>>(&expr, None)
+                    .unwrap()
+            })
+        });
+    }
+}
+
+criterion_group!(benches, records);
+criterion_main!(benches);
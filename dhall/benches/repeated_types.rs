@@ -0,0 +1,28 @@
+//! Typechecking configs that repeat the same large record type, which
+//! interning of record types speeds up. Run with
+//! `cargo bench --bench repeated_types`.
+use criterion::{criterion_group, criterion_main, Criterion};
+use dhall::de::Value;
+
+/// A list of `n` records with the same `fields` fields, each annotated with
+/// the record type.
+fn repeated_records(n: usize, fields: usize) -> String {
+    let ty: Vec<_> = (0..fields).map(|i| format!("f{} : Natural", i)).collect();
+    let value: Vec<_> =
+        (0..fields).map(|i| format!("f{} = {}", i, i)).collect();
+    let record =
+        format!("({{ {} }} : {{ {} }})", value.join(", "), ty.join(", "));
+    format!("[ {} ]", vec![record; n].join(", "))
+}
+
+fn repeated_types(c: &mut Criterion) {
+    for &n in &[10, 100] {
+        let expr = repeated_records(n, 50);
+        c.bench_function(&format!("repeated_record_type_{}", n), move |b| {
+            b.iter(|| Value::from_str(&expr, None).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, repeated_types);
+criterion_main!(benches);
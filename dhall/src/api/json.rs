@@ -0,0 +1,586 @@
+//! Conversion of Dhall values to and from JSON and YAML, following the
+//! conventions of the standard `dhall-to-json` and `json-to-dhall` tools.
+//!
+//! Records become objects, `Some x` becomes `x` and `None` becomes `null`, and
+//! union alternatives are unwrapped, or become their name if they are empty.
+//! Lists of `{ mapKey : Text, mapValue : T }` records, as built by `toMap`,
+//! become objects. Values of the `JSON` type from the Prelude become the JSON
+//! they describe. Fields of objects that are `null` are omitted, unless
+//! [JsonOptions::preserve_null] is set.
+use num_traits::ToPrimitive;
+use serde_json::{Map, Number, Value as Json};
+use serde_yaml::Value as Yaml;
+
+use dhall_syntax::{rc, Builtin, Const, ExprF, Label, V};
+
+use crate::api::{Type, Value};
+use crate::error::{ConversionError, Result};
+use crate::phase::{NormalizedSubExpr, Resolved};
+
+/// Options for [to_json_with] and [to_yaml_with].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonOptions {
+    /// Keeps the fields of objects that are `null`, e.g. record fields that
+    /// are `None`, instead of omitting them.
+    pub preserve_null: bool,
+}
+
+/// Converts a value to JSON. Fails if the value contains something that
+/// JSON cannot represent, like a function.
+pub fn to_json(v: &Value) -> Result<Json> {
+    to_json_with(v, &JsonOptions::default())
+}
+
+/// Like [to_json], with the given options.
+pub fn to_json_with(v: &Value, options: &JsonOptions) -> Result<Json> {
+    let mut json = expr_to_json(&v.to_expr())?;
+    if !options.preserve_null {
+        omit_null(&mut json);
+    }
+    Ok(json)
+}
+
+/// Converts a value to YAML, the same way as [to_json].
+pub fn to_yaml(v: &Value) -> Result<Yaml> {
+    to_yaml_with(v, &JsonOptions::default())
+}
+
+/// Like [to_yaml], with the given options.
+pub fn to_yaml_with(v: &Value, options: &JsonOptions) -> Result<Yaml> {
+    Ok(json_to_yaml(to_json_with(v, options)?))
+}
+
+/// Converts JSON to a value of type `ty`. The type settles what JSON leaves
+/// ambiguous: whether a number is a `Natural`, an `Integer` or a `Double`,
+/// which alternative of a union a value belongs to, and the type of empty
+/// lists. Where an `Optional` is expected, `null` and missing fields become
+/// `None`. For unions, the first alternative that fits is picked.
+pub fn from_json(json: &Json, ty: &Type) -> Result<Value> {
+    let expr = json_to_expr(json, &ty.to_expr())?;
+    Value::from_resolved(Resolved::from_normalized_expr(expr), Some(ty))
+}
+
+/// Converts YAML to a value of type `ty`, the same way as [from_json].
+pub fn from_yaml(yaml: &Yaml, ty: &Type) -> Result<Value> {
+    from_json(&yaml_to_json(yaml)?, ty)
+}
+
+fn expr_to_json(e: &NormalizedSubExpr) -> Result<Json, ConversionError> {
+    use ExprF::*;
+    Ok(match e.as_ref() {
+        BoolLit(b) => Json::Bool(*b),
+        NaturalLit(n) => match n.to_u64() {
+            Some(n) => Json::from(n),
+            None => return Err(out_of_range(n)),
+        },
+        IntegerLit(n) => match n.to_i64() {
+            Some(n) => Json::from(n),
+            None => return Err(out_of_range(n)),
+        },
+        DoubleLit(x) => {
+            let x: f64 = (*x).into();
+            match Number::from_f64(x) {
+                Some(x) => Json::Number(x),
+                None => return Err(out_of_range(&x)),
+            }
+        }
+        TextLit(t) if t.len() == 1 => Json::String(t.head().to_owned()),
+        RecordLit(m) => Json::Object(
+            m.iter()
+                .map(|(k, v)| {
+                    let v =
+                        expr_to_json(v).map_err(|e| e.at_field(k.as_ref()))?;
+                    Ok((String::from(k), v))
+                })
+                .collect::<Result<_, ConversionError>>()?,
+        ),
+        EmptyListLit(t) if is_map_entry_type(t) => Json::Object(Map::new()),
+        EmptyListLit(_) => Json::Array(vec![]),
+        NEListLit(xs) if xs.iter().all(|x| map_entry(x).is_some()) => {
+            map_to_json(xs, expr_to_json)?
+        }
+        NEListLit(xs) => list_to_json(xs, expr_to_json)?,
+        SomeLit(x) => expr_to_json(x)?,
+        App(f, _) if is_builtin(f, Builtin::OptionalNone) => Json::Null,
+        UnionLit(_, x, _) => expr_to_json(x)?,
+        Field(u, l) if is_union_type(u) => Json::String(String::from(l)),
+        App(f, x) => match f.as_ref() {
+            Field(u, _) if is_union_type(u) => expr_to_json(x)?,
+            _ => return Err(cannot_convert(e)),
+        },
+        Lam(_, t, body) => match (t.as_ref(), body.as_ref()) {
+            (Const(Const::Type), Lam(json, _, body)) => {
+                prelude_json_to_json(body, json)?
+            }
+            _ => return Err(cannot_convert(e)),
+        },
+        _ => return Err(cannot_convert(e)),
+    })
+}
+
+/// Converts the body of a value of the Prelude's `JSON` type, i.e. of
+/// `λ(JSON : Type) → λ(json : { array : …, bool : …, … }) → body`. `json` is
+/// the name of the record of constructors.
+fn prelude_json_to_json(
+    e: &NormalizedSubExpr,
+    json: &Label,
+) -> Result<Json, ConversionError> {
+    use ExprF::*;
+    // The name of the constructor `e` is, if it is one
+    let constructor = |e: &NormalizedSubExpr| match e.as_ref() {
+        Field(r, name) => match r.as_ref() {
+            Var(V(l, 0)) if l == json => Some(String::from(name)),
+            _ => None,
+        },
+        _ => None,
+    };
+    let go = |e: &NormalizedSubExpr| prelude_json_to_json(e, json);
+    if constructor(e).as_ref().map(String::as_str) == Some("null") {
+        return Ok(Json::Null);
+    }
+    let (name, x) = match e.as_ref() {
+        App(f, x) => match constructor(f) {
+            Some(name) => (name, x),
+            None => return Err(cannot_convert(e)),
+        },
+        _ => return Err(cannot_convert(e)),
+    };
+    Ok(match (name.as_str(), x.as_ref()) {
+        ("array", EmptyListLit(_)) => Json::Array(vec![]),
+        ("array", NEListLit(xs)) => list_to_json(xs, go)?,
+        ("object", EmptyListLit(_)) => Json::Object(Map::new()),
+        ("object", NEListLit(xs)) => map_to_json(xs, go)?,
+        ("bool", _)
+        | ("double", _)
+        | ("integer", _)
+        | ("natural", _)
+        | ("number", _)
+        | ("string", _) => expr_to_json(x)?,
+        _ => return Err(cannot_convert(e)),
+    })
+}
+
+fn list_to_json(
+    xs: &[NormalizedSubExpr],
+    convert: impl Fn(&NormalizedSubExpr) -> Result<Json, ConversionError>,
+) -> Result<Json, ConversionError> {
+    Ok(Json::Array(
+        xs.iter()
+            .enumerate()
+            .map(|(i, x)| convert(x).map_err(|e| e.at_index(i)))
+            .collect::<Result<_, _>>()?,
+    ))
+}
+
+/// Converts a list of `mapKey`/`mapValue` records to an object.
+fn map_to_json(
+    xs: &[NormalizedSubExpr],
+    convert: impl Fn(&NormalizedSubExpr) -> Result<Json, ConversionError>,
+) -> Result<Json, ConversionError> {
+    let mut object = Map::new();
+    for (i, x) in xs.iter().enumerate() {
+        let (k, v) = match map_entry(x) {
+            Some(entry) => entry,
+            None => return Err(cannot_convert(x).at_index(i)),
+        };
+        if object.contains_key(&k) {
+            let message = format!("Duplicate key `{}`", k);
+            return Err(ConversionError::new(message).at_index(i));
+        }
+        let v = convert(v).map_err(|e| e.at_field(&k))?;
+        object.insert(k, v);
+    }
+    Ok(Json::Object(object))
+}
+
+/// The key and value of a `{ mapKey = k, mapValue = v }` record.
+fn map_entry(e: &NormalizedSubExpr) -> Option<(String, &NormalizedSubExpr)> {
+    let fields = match e.as_ref() {
+        ExprF::RecordLit(m) if m.len() == 2 => m,
+        _ => return None,
+    };
+    let (mut key, mut value) = (None, None);
+    for (k, v) in fields {
+        match k.as_ref() {
+            "mapKey" => key = Some(v),
+            "mapValue" => value = Some(v),
+            _ => return None,
+        }
+    }
+    let key = match key?.as_ref() {
+        ExprF::TextLit(t) if t.len() == 1 => t.head().to_owned(),
+        _ => return None,
+    };
+    Some((key, value?))
+}
+
+/// Whether `t` is `{ mapKey : Text, mapValue : T }` for some `T`.
+fn is_map_entry_type(t: &NormalizedSubExpr) -> bool {
+    match t.as_ref() {
+        ExprF::RecordType(m) if m.len() == 2 => {
+            m.iter().all(|(k, v)| match k.as_ref() {
+                "mapKey" => is_builtin(v, Builtin::Text),
+                "mapValue" => true,
+                _ => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+fn is_builtin(e: &NormalizedSubExpr, b: Builtin) -> bool {
+    match e.as_ref() {
+        ExprF::Builtin(b2) => *b2 == b,
+        _ => false,
+    }
+}
+
+fn is_union_type(e: &NormalizedSubExpr) -> bool {
+    match e.as_ref() {
+        ExprF::UnionType(_) => true,
+        _ => false,
+    }
+}
+
+fn out_of_range(n: &impl std::fmt::Display) -> ConversionError {
+    ConversionError::new(format!("{} cannot be represented in JSON", n))
+}
+
+fn cannot_convert(e: &NormalizedSubExpr) -> ConversionError {
+    let message = match e.as_ref() {
+        ExprF::Lam(_, _, _) => {
+            "Functions cannot be converted to JSON".to_owned()
+        }
+        _ => format!("`{}` cannot be converted to JSON", e),
+    };
+    ConversionError::new(message)
+}
+
+/// Removes the `null` fields of all the objects in `j`.
+fn omit_null(j: &mut Json) {
+    match j {
+        Json::Array(xs) => xs.iter_mut().for_each(omit_null),
+        Json::Object(m) => {
+            *m = std::mem::replace(m, Map::new())
+                .into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, mut v)| {
+                    omit_null(&mut v);
+                    (k, v)
+                })
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+fn json_to_yaml(j: Json) -> Yaml {
+    match j {
+        Json::Null => Yaml::Null,
+        Json::Bool(b) => Yaml::Bool(b),
+        Json::Number(n) => Yaml::Number(match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => n.into(),
+            (_, Some(n)) => n.into(),
+            // Without arbitrary precision, all JSON numbers fit in an f64
+            _ => n.as_f64().unwrap_or(std::f64::NAN).into(),
+        }),
+        Json::String(s) => Yaml::String(s),
+        Json::Array(xs) => {
+            Yaml::Sequence(xs.into_iter().map(json_to_yaml).collect())
+        }
+        Json::Object(m) => Yaml::Mapping(
+            m.into_iter()
+                .map(|(k, v)| (Yaml::String(k), json_to_yaml(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn json_to_expr(
+    json: &Json,
+    ty: &NormalizedSubExpr,
+) -> Result<NormalizedSubExpr, ConversionError> {
+    use ExprF::*;
+    let mismatch = || {
+        ConversionError::new(format!(
+            "Expected a value of type `{}`, got `{}`",
+            ty, json
+        ))
+    };
+    Ok(rc(match (ty.as_ref(), json) {
+        (Builtin(Builtin::Bool), Json::Bool(b)) => BoolLit(*b),
+        (Builtin(Builtin::Natural), Json::Number(n)) => match n.as_u64() {
+            Some(n) => NaturalLit(n.into()),
+            None => return Err(mismatch()),
+        },
+        (Builtin(Builtin::Integer), Json::Number(n)) => match n.as_i64() {
+            Some(n) => IntegerLit(n.into()),
+            None => return Err(mismatch()),
+        },
+        (Builtin(Builtin::Double), Json::Number(n)) => match n.as_f64() {
+            Some(x) => DoubleLit(x.into()),
+            None => return Err(mismatch()),
+        },
+        (Builtin(Builtin::Text), Json::String(s)) => TextLit(s.clone().into()),
+        (App(f, t), _) if is_builtin(f, Builtin::Optional) => match json {
+            Json::Null => App(rc(Builtin(Builtin::OptionalNone)), t.clone()),
+            _ => SomeLit(json_to_expr(json, t)?),
+        },
+        (App(f, t), Json::Object(m))
+            if is_builtin(f, Builtin::List) && is_map_entry_type(t) =>
+        {
+            let value_ty = match t.as_ref() {
+                RecordType(kts) => kts
+                    .iter()
+                    .find(|(k, _)| k.as_ref() == "mapValue")
+                    .map(|(_, t)| t)
+                    .unwrap(),
+                _ => unreachable!(),
+            };
+            let entries = m
+                .iter()
+                .map(|(k, v)| {
+                    let v =
+                        json_to_expr(v, value_ty).map_err(|e| e.at_field(k))?;
+                    let key = rc(TextLit(k.clone().into()));
+                    Ok(rc(RecordLit(
+                        vec![("mapKey".into(), key), ("mapValue".into(), v)]
+                            .into_iter()
+                            .collect(),
+                    )))
+                })
+                .collect::<Result<Vec<_>, ConversionError>>()?;
+            if entries.is_empty() {
+                EmptyListLit(t.clone())
+            } else {
+                NEListLit(entries)
+            }
+        }
+        (App(f, t), Json::Array(xs)) if is_builtin(f, Builtin::List) => {
+            if xs.is_empty() {
+                EmptyListLit(t.clone())
+            } else {
+                NEListLit(
+                    xs.iter()
+                        .enumerate()
+                        .map(|(i, x)| {
+                            json_to_expr(x, t).map_err(|e| e.at_index(i))
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+        }
+        (RecordType(kts), Json::Object(m)) => {
+            if let Some(k) = m
+                .keys()
+                .find(|k| !kts.iter().any(|(l, _)| l.as_ref() == *k))
+            {
+                let e = ConversionError::new("Unexpected field".to_owned());
+                return Err(e.at_field(k));
+            }
+            RecordLit(
+                kts.iter()
+                    .map(|(k, t)| {
+                        let v = match m.get(k.as_ref()) {
+                            Some(v) => json_to_expr(v, t),
+                            None if is_optional_type(t) => {
+                                json_to_expr(&Json::Null, t)
+                            }
+                            None => Err(ConversionError::new(
+                                "Missing field".to_owned(),
+                            )),
+                        };
+                        Ok((k.clone(), v.map_err(|e| e.at_field(k.as_ref()))?))
+                    })
+                    .collect::<Result<_, ConversionError>>()?,
+            )
+        }
+        (UnionType(kts), _) => {
+            // The alternative that failed the deepest inside the value, and
+            // its error
+            let mut closest: Option<(&Label, ConversionError)> = None;
+            for (k, t) in kts {
+                let alternative = rc(Field(ty.clone(), k.clone()));
+                match t {
+                    None if json.as_str() == Some(k.as_ref()) => {
+                        return Ok(alternative);
+                    }
+                    Some(t) => match json_to_expr(json, t) {
+                        Ok(v) => return Ok(rc(App(alternative, v))),
+                        Err(e) => {
+                            let is_closer = match &closest {
+                                Some((_, c)) => depth(&e) > depth(c),
+                                None => depth(&e) > 0,
+                            };
+                            if is_closer {
+                                closest = Some((k, e));
+                            }
+                        }
+                    },
+                    None => {}
+                }
+            }
+            return Err(match closest {
+                Some((k, mut e)) => {
+                    e.message = format!(
+                        "{}, for the alternative `{}` of `{}`, which comes \
+                         closest",
+                        e.message, k, ty
+                    );
+                    e
+                }
+                None => ConversionError::new(format!(
+                    "`{}` matches no alternative of `{}`",
+                    json, ty
+                )),
+            });
+        }
+        (Pi(json_ty, k, rest), _) => match (k.as_ref(), rest.as_ref()) {
+            (Const(Const::Type), Pi(constructors, fields, _)) => Lam(
+                json_ty.clone(),
+                k.clone(),
+                rc(Lam(
+                    constructors.clone(),
+                    fields.clone(),
+                    json_to_prelude_json(json, json_ty, constructors, fields),
+                )),
+            ),
+            _ => return Err(mismatch()),
+        },
+        _ => return Err(mismatch()),
+    }))
+}
+
+/// How many fields or list elements deep inside the value an error is.
+fn depth(e: &ConversionError) -> usize {
+    e.path.matches(|c| c == '.' || c == '[').count()
+}
+
+/// Builds the body of a value of the Prelude's `JSON` type, where `json_ty`
+/// is the name of the `JSON` type and `constructors` the name of the record
+/// of constructors, whose type is `fields`.
+fn json_to_prelude_json(
+    json: &Json,
+    json_ty: &Label,
+    constructors: &Label,
+    fields: &NormalizedSubExpr,
+) -> NormalizedSubExpr {
+    use ExprF::*;
+    let go =
+        |json: &Json| json_to_prelude_json(json, json_ty, constructors, fields);
+    let constructor = |name: &str| {
+        rc(Field(rc(Var(V(constructors.clone(), 0))), name.into()))
+    };
+    let app = |name: &str, x| rc(App(constructor(name), rc(x)));
+    // The `JSON` variable, from under the binder of the constructors
+    let json_ty = {
+        let shift = if json_ty == constructors { 1 } else { 0 };
+        rc(Var(V(json_ty.clone(), shift)))
+    };
+    // Versions of the type differ in their constructors for numbers: older
+    // ones only have `number`, newer ones `integer` and `double`, and some
+    // also `natural`.
+    let has_constructor = |name: &str| match fields.as_ref() {
+        RecordType(kts) => kts.iter().any(|(k, _)| k.as_ref() == name),
+        _ => false,
+    };
+    match json {
+        Json::Null => constructor("null"),
+        Json::Bool(b) => app("bool", BoolLit(*b)),
+        Json::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) if has_constructor("natural") => {
+                app("natural", NaturalLit(n.into()))
+            }
+            (_, Some(n)) if has_constructor("integer") => {
+                app("integer", IntegerLit(n.into()))
+            }
+            _ => {
+                let x = DoubleLit(n.as_f64().unwrap_or(std::f64::NAN).into());
+                if has_constructor("double") {
+                    app("double", x)
+                } else {
+                    app("number", x)
+                }
+            }
+        },
+        Json::String(s) => app("string", TextLit(s.clone().into())),
+        Json::Array(xs) if xs.is_empty() => app("array", EmptyListLit(json_ty)),
+        Json::Array(xs) => app("array", NEListLit(xs.iter().map(go).collect())),
+        Json::Object(m) => {
+            let entries: Vec<_> = m
+                .iter()
+                .map(|(k, v)| {
+                    let key = rc(TextLit(k.clone().into()));
+                    rc(RecordLit(
+                        vec![
+                            ("mapKey".into(), key),
+                            ("mapValue".into(), go(v)),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    ))
+                })
+                .collect();
+            if entries.is_empty() {
+                let entry_ty = vec![
+                    ("mapKey".into(), rc(Builtin(Builtin::Text))),
+                    ("mapValue".into(), json_ty),
+                ];
+                let entry_ty = rc(RecordType(entry_ty.into_iter().collect()));
+                app("object", EmptyListLit(entry_ty))
+            } else {
+                app("object", NEListLit(entries))
+            }
+        }
+    }
+}
+
+fn is_optional_type(t: &NormalizedSubExpr) -> bool {
+    match t.as_ref() {
+        ExprF::App(f, _) => is_builtin(f, Builtin::Optional),
+        _ => false,
+    }
+}
+
+fn yaml_to_json(yaml: &Yaml) -> Result<Json, ConversionError> {
+    Ok(match yaml {
+        Yaml::Null => Json::Null,
+        Yaml::Bool(b) => Json::Bool(*b),
+        Yaml::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => Json::from(n),
+            (_, Some(n)) => Json::from(n),
+            _ => match n.as_f64().and_then(Number::from_f64) {
+                Some(x) => Json::Number(x),
+                None => {
+                    return Err(ConversionError::new(format!(
+                        "{} cannot be represented in Dhall",
+                        n
+                    )))
+                }
+            },
+        },
+        Yaml::String(s) => Json::String(s.clone()),
+        Yaml::Sequence(xs) => Json::Array(
+            xs.iter()
+                .enumerate()
+                .map(|(i, x)| yaml_to_json(x).map_err(|e| e.at_index(i)))
+                .collect::<Result<_, _>>()?,
+        ),
+        Yaml::Mapping(m) => Json::Object(
+            m.iter()
+                .map(|(k, v)| {
+                    let k = match k {
+                        Yaml::String(k) => k.clone(),
+                        _ => {
+                            return Err(ConversionError::new(
+                                "Only strings can be keys".to_owned(),
+                            ))
+                        }
+                    };
+                    let v = yaml_to_json(v).map_err(|e| e.at_field(&k))?;
+                    Ok((k, v))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+    })
+}
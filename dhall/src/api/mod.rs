@@ -1,26 +1,54 @@
+pub mod json;
 mod serde;
 pub(crate) mod static_type;
 
 pub use value::Value;
 
 mod value {
+    use std::path::Path;
+
     use super::Type;
     use crate::error::Result;
-    use crate::phase::{NormalizedSubExpr, Parsed, Typed};
+    use crate::phase::{NormalizedSubExpr, Parsed, Resolved, Typed};
 
     // A Dhall value
     pub struct Value(Typed);
 
     impl Value {
         pub fn from_str(s: &str, ty: Option<&Type>) -> Result<Self> {
-            let resolved = Parsed::parse_str(s)?.resolve()?;
+            Self::from_parsed(Parsed::parse_str(s)?, ty)
+        }
+        /// Like [Value::from_str], but reads the file at `f`. Relative
+        /// imports are resolved relative to that file.
+        pub fn from_file(f: &Path, ty: Option<&Type>) -> Result<Self> {
+            Self::from_parsed(Parsed::parse_file(f)?, ty)
+        }
+        /// Decodes a value from the CBOR binary format, e.g. as produced by
+        /// [Value::to_cbor]. Any imports are resolved.
+        pub fn from_cbor(data: &[u8], ty: Option<&Type>) -> Result<Self> {
+            Self::from_parsed(Parsed::parse_binary(data)?, ty)
+        }
+        fn from_parsed(parsed: Parsed, ty: Option<&Type>) -> Result<Self> {
+            Self::from_resolved(parsed.resolve()?, ty)
+        }
+        pub(crate) fn from_resolved(
+            resolved: Resolved,
+            ty: Option<&Type>,
+        ) -> Result<Self> {
             let typed = match ty {
                 None => resolved.typecheck()?,
                 Some(t) => resolved.typecheck_with(&t.to_type())?,
             };
             Ok(Value(typed))
         }
-        pub(crate) fn to_expr(&self) -> NormalizedSubExpr {
+        /// Encodes the normal form of this value to the CBOR binary format.
+        /// The result contains no imports, so it can be loaded without
+        /// access to the files or URLs the value was built from.
+        pub fn to_cbor(&self) -> Result<Vec<u8>> {
+            Ok(self.0.encode()?)
+        }
+        /// The normal form of the value, e.g. to print it.
+        pub fn to_expr(&self) -> NormalizedSubExpr {
             self.0.to_expr()
         }
         pub(crate) fn to_typed(&self) -> Typed {
@@ -115,6 +143,7 @@ mod typ {
 pub mod de {
     pub use super::static_type::StaticType;
     pub use super::{Type, Value};
+    pub use crate::core::limits::EvalLimits;
     use crate::error::Result;
     #[doc(hidden)]
     pub use dhall_proc_macros::StaticType;
@@ -160,4 +189,22 @@ pub mod de {
     {
         from_str(s, Some(&<T as StaticType>::static_type()))
     }
+
+    /// Deserialize an instance of type T from a string of Dhall text, bounding
+    /// the work that normalization may do.
+    ///
+    /// This behaves like [from_str][crate::de::from_str], except that
+    /// exceeding one of the `limits` at any point, including while
+    /// typechecking imported files, returns
+    /// [Error::ResourceLimit][crate::error::Error::ResourceLimit].
+    pub fn from_str_with_limits<T>(
+        s: &str,
+        ty: Option<&Type>,
+        limits: &EvalLimits,
+    ) -> Result<T>
+    where
+        T: Deserialize,
+    {
+        limits.run(|| from_str(s, ty))
+    }
 }
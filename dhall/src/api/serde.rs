@@ -1,6 +1,7 @@
 use crate::api::de::{Deserialize, Value};
 use crate::error::{Error, Result};
 use dhall_syntax::{ExprF, SubExpr, X};
+use num_traits::ToPrimitive;
 use std::borrow::Cow;
 
 impl<'a, T> Deserialize for T
@@ -30,28 +31,49 @@ impl<'de: 'a, 'a> serde::de::IntoDeserializer<'de, Error> for Deserializer<'a> {
     }
 }
 
+fn out_of_range(n: &impl std::fmt::Display, ty: &str) -> Error {
+    Error::Deserialize(format!("{} is out of range for type `{}`", n, ty))
+}
+
+/// Deserializes Naturals and Integers into the requested Rust integer type,
+/// failing if the value does not fit.
+macro_rules! deserialize_integer {
+    ($($deserialize:ident, $visit:ident, $convert:ident, $ty:expr;)*) => {$(
+        fn $deserialize<V>(self, visitor: V) -> Result<V::Value>
+        where
+            V: serde::de::Visitor<'de>,
+        {
+            use ExprF::*;
+            match self.0.as_ref().as_ref() {
+                NaturalLit(n) => match n.$convert() {
+                    Some(n) => visitor.$visit(n),
+                    None => Err(out_of_range(n, $ty)),
+                },
+                IntegerLit(n) => match n.$convert() {
+                    Some(n) => visitor.$visit(n),
+                    None => Err(out_of_range(n, $ty)),
+                },
+                _ => self.deserialize_any(visitor),
+            }
+        }
+    )*};
+}
+
 impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
     type Error = Error;
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: serde::de::Visitor<'de>,
     {
-        use std::convert::TryInto;
         use ExprF::*;
         match self.0.as_ref().as_ref() {
-            NaturalLit(n) => match (*n).try_into() {
-                Ok(n64) => visitor.visit_u64(n64),
-                Err(_) => match (*n).try_into() {
-                    Ok(n32) => visitor.visit_u32(n32),
-                    Err(_) => unimplemented!(),
-                },
+            NaturalLit(n) => match n.to_u64() {
+                Some(n) => visitor.visit_u64(n),
+                None => Err(out_of_range(n, "u64")),
             },
-            IntegerLit(n) => match (*n).try_into() {
-                Ok(n64) => visitor.visit_i64(n64),
-                Err(_) => match (*n).try_into() {
-                    Ok(n32) => visitor.visit_i32(n32),
-                    Err(_) => unimplemented!(),
-                },
+            IntegerLit(n) => match n.to_i64() {
+                Some(n) => visitor.visit_i64(n),
+                None => Err(out_of_range(n, "i64")),
             },
             RecordLit(m) => visitor.visit_map(
                 serde::de::value::MapDeserializer::new(m.iter().map(
@@ -62,8 +84,21 @@ impl<'de: 'a, 'a> serde::Deserializer<'de> for Deserializer<'a> {
         }
     }
 
+    deserialize_integer! {
+        deserialize_i8, visit_i8, to_i8, "i8";
+        deserialize_i16, visit_i16, to_i16, "i16";
+        deserialize_i32, visit_i32, to_i32, "i32";
+        deserialize_i64, visit_i64, to_i64, "i64";
+        deserialize_i128, visit_i128, to_i128, "i128";
+        deserialize_u8, visit_u8, to_u8, "u8";
+        deserialize_u16, visit_u16, to_u16, "u16";
+        deserialize_u32, visit_u32, to_u32, "u32";
+        deserialize_u64, visit_u64, to_u64, "u64";
+        deserialize_u128, visit_u128, to_u128, "u128";
+    }
+
     serde::forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bool f32 f64 char str string
         bytes byte_buf option unit unit_struct newtype_struct seq tuple
         tuple_struct map struct enum identifier ignored_any
     }
@@ -0,0 +1,507 @@
+//! [proptest] strategies that generate Dhall expressions, for property-based
+//! testing.
+//!
+//! [expr] covers the whole syntax and is meant for testing the parser, the
+//! printer and the binary encoding. Most of its outputs are ill-typed, so
+//! [well_typed_expr] generates closed, well-typed expressions instead, for
+//! testing typechecking and normalization. Both shrink towards smaller
+//! expressions.
+//!
+//! This module is available to other crates with the `arbitrary` feature.
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn encode_decode(e in dhall::arbitrary::expr()) {
+//!         // ...
+//!     }
+//! }
+//! ```
+use num_bigint::{BigInt, BigUint, Sign};
+use proptest::prelude::*;
+use proptest::strategy::Union;
+
+use dhall_syntax::map::DupTreeMap;
+use dhall_syntax::{
+    rc, BinOp, Builtin, Const, ExprF, FilePrefix, Hash, Import, ImportHashed,
+    ImportLocation, ImportMode, InterpolatedText, Label, NaiveDouble, Scheme,
+    SubExpr, URL, V, X,
+};
+
+/// The expressions generated by this module, i.e. decoded expressions.
+pub type ArbExpr = SubExpr<X, Import>;
+
+/// Labels, biased towards the ones that are tricky to print.
+pub fn label() -> impl Strategy<Value = Label> {
+    let tricky = vec![
+        "if",
+        "Some",
+        "Natural",
+        "Type",
+        "None",
+        "foo-bar",
+        "a/b",
+        "with space",
+        "1st",
+    ];
+    prop_oneof![
+        4 => prop::sample::select(vec!["x", "y", "_"]).prop_map(Label::from),
+        1 => prop::sample::select(tricky).prop_map(Label::from),
+        // The characters allowed in quoted labels
+        1 => "[ -_a-~]{1,4}".prop_map(Label::from),
+    ]
+}
+
+pub fn builtin() -> impl Strategy<Value = Builtin> {
+    use Builtin::*;
+    prop::sample::select(vec![
+        Bool,
+        Natural,
+        Integer,
+        Double,
+        Text,
+        List,
+        Optional,
+        OptionalNone,
+        NaturalBuild,
+        NaturalFold,
+        NaturalIsZero,
+        NaturalEven,
+        NaturalOdd,
+        NaturalToInteger,
+        NaturalShow,
+        IntegerToDouble,
+        IntegerShow,
+        DoubleShow,
+        ListBuild,
+        ListFold,
+        ListLength,
+        ListHead,
+        ListLast,
+        ListIndexed,
+        ListReverse,
+        OptionalFold,
+        OptionalBuild,
+        TextShow,
+    ])
+}
+
+pub fn binop() -> impl Strategy<Value = BinOp> {
+    use BinOp::*;
+    prop::sample::select(vec![
+        ImportAlt,
+        BoolOr,
+        NaturalPlus,
+        TextAppend,
+        ListAppend,
+        BoolAnd,
+        RecursiveRecordMerge,
+        RightBiasedRecordMerge,
+        RecursiveRecordTypeMerge,
+        NaturalTimes,
+        BoolEQ,
+        BoolNE,
+    ])
+}
+
+pub fn double() -> impl Strategy<Value = NaiveDouble> {
+    prop_oneof![
+        4 => prop::num::f64::NORMAL,
+        1 => Just(std::f64::INFINITY),
+        1 => Just(std::f64::NEG_INFINITY),
+        1 => Just(std::f64::NAN),
+    ]
+    .prop_map(NaiveDouble::from)
+}
+
+/// Naturals that do not fit in 64 bits, which are stored and encoded
+/// differently from the others.
+pub fn big_natural() -> impl Strategy<Value = BigUint> {
+    prop::collection::vec(any::<u8>(), 9..24).prop_map(|mut bytes| {
+        // A nonzero leading byte makes the number at least 2^64
+        bytes[0] |= 1;
+        BigUint::from_bytes_be(&bytes)
+    })
+}
+
+/// Integers that do not fit in 64 bits, of either sign.
+pub fn big_integer() -> impl Strategy<Value = BigInt> {
+    (any::<bool>(), big_natural()).prop_map(|(negative, n)| {
+        let sign = if negative { Sign::Minus } else { Sign::Plus };
+        BigInt::from_biguint(sign, n)
+    })
+}
+
+/// The contents of a text literal, including characters that need escaping.
+pub fn text() -> impl Strategy<Value = String> {
+    "[ -~\n\tλ]{0,6}"
+}
+
+pub fn import() -> impl Strategy<Value = Import> {
+    let component = || prop::collection::vec("[a-z]{1,5}", 1..3);
+    let prefix = prop::sample::select(vec![
+        FilePrefix::Absolute,
+        FilePrefix::Here,
+        FilePrefix::Parent,
+        FilePrefix::Home,
+    ]);
+    let local = (prefix, component())
+        .prop_map(|(prefix, path)| ImportLocation::Local(prefix, path));
+    let remote = (any::<bool>(), component()).prop_map(|(https, path)| {
+        ImportLocation::Remote(URL {
+            scheme: if https { Scheme::HTTPS } else { Scheme::HTTP },
+            authority: "example.com".to_owned(),
+            path,
+            query: None,
+            headers: None,
+        })
+    });
+    let location = prop_oneof![
+        local,
+        remote,
+        "[A-Z_]{1,5}".prop_map(ImportLocation::Env),
+        Just(ImportLocation::Missing),
+    ];
+    let hash = prop::option::of("[0-9a-f]{64}".prop_map(|hash| Hash {
+        protocol: "sha256".to_owned(),
+        hash,
+    }));
+    let mode =
+        prop::sample::select(vec![ImportMode::Code, ImportMode::RawText]);
+    (location, hash, mode).prop_map(|(location, hash, mode)| Import {
+        mode,
+        location_hashed: ImportHashed { location, hash },
+    })
+}
+
+fn map<T: std::fmt::Debug>(
+    values: impl Strategy<Value = T>,
+) -> impl Strategy<Value = DupTreeMap<Label, T>> {
+    prop::collection::vec((label(), values), 0..3)
+        .prop_map(|kvs| kvs.into_iter().collect())
+}
+
+/// Arbitrary expressions, well-typed or not.
+pub fn expr() -> BoxedStrategy<ArbExpr> {
+    use ExprF::*;
+    let consts =
+        prop::sample::select(vec![Const::Type, Const::Kind, Const::Sort]);
+    let leaf = prop_oneof![
+        (label(), 0..3usize).prop_map(|(l, n)| Var(V(l, n))),
+        consts.prop_map(Const),
+        builtin().prop_map(Builtin),
+        any::<bool>().prop_map(BoolLit),
+        any::<u64>().prop_map(|n| NaturalLit(n.into())),
+        any::<i64>().prop_map(|n| IntegerLit(n.into())),
+        big_natural().prop_map(|n| NaturalLit(n.into())),
+        big_integer().prop_map(|n| IntegerLit(n.into())),
+        double().prop_map(DoubleLit),
+        text().prop_map(|s| TextLit(s.into())),
+        import().prop_map(Embed),
+    ]
+    .prop_map(rc);
+    leaf.prop_recursive(6, 64, 4, |e| {
+        let interpolated =
+            (text(), prop::collection::vec((e.clone(), text()), 1..3))
+                .prop_map(|t| TextLit(InterpolatedText::from(t)));
+        let choices = vec![
+            (label(), e.clone(), e.clone())
+                .prop_map(|(l, t, b)| Lam(l, t, b))
+                .boxed(),
+            (label(), e.clone(), e.clone())
+                .prop_map(|(l, t, b)| Pi(l, t, b))
+                .boxed(),
+            (e.clone(), e.clone()).prop_map(|(f, a)| App(f, a)).boxed(),
+            (label(), prop::option::of(e.clone()), e.clone(), e.clone())
+                .prop_map(|(l, t, v, b)| Let(l, t, v, b))
+                .boxed(),
+            (e.clone(), e.clone())
+                .prop_map(|(x, t)| Annot(x, t))
+                .boxed(),
+            (binop(), e.clone(), e.clone())
+                .prop_map(|(op, a, b)| BinOp(op, a, b))
+                .boxed(),
+            (e.clone(), e.clone(), e.clone())
+                .prop_map(|(c, t, f)| BoolIf(c, t, f))
+                .boxed(),
+            interpolated.boxed(),
+            e.clone().prop_map(EmptyListLit).boxed(),
+            prop::collection::vec(e.clone(), 1..4)
+                .prop_map(NEListLit)
+                .boxed(),
+            e.clone().prop_map(SomeLit).boxed(),
+            map(e.clone()).prop_map(RecordType).boxed(),
+            map(e.clone()).prop_map(RecordLit).boxed(),
+            map(prop::option::of(e.clone())).prop_map(UnionType).boxed(),
+            (label(), e.clone(), map(prop::option::of(e.clone())))
+                .prop_map(|(l, x, alts)| UnionLit(l, x, alts))
+                .boxed(),
+            (e.clone(), e.clone(), prop::option::of(e.clone()))
+                .prop_map(|(x, y, t)| Merge(x, y, t))
+                .boxed(),
+            (e.clone(), label()).prop_map(|(x, l)| Field(x, l)).boxed(),
+            (e, prop::collection::vec(label(), 0..3))
+                .prop_map(|(x, ls)| Projection(x, ls))
+                .boxed(),
+        ];
+        Union::new(choices).prop_map(rc)
+    })
+    .boxed()
+}
+
+/// The types of the expressions generated by [well_typed_expr].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Ty {
+    Bool,
+    Natural,
+    Text,
+    List(Box<Ty>),
+    Optional(Box<Ty>),
+    Record(Vec<(Label, Ty)>),
+    Fun(Box<Ty>, Box<Ty>),
+}
+
+/// The variables in scope, innermost last.
+type Ctx = Vec<(Label, Ty)>;
+
+fn any_ty() -> impl Strategy<Value = Ty> {
+    let leaf = prop_oneof![Just(Ty::Bool), Just(Ty::Natural), Just(Ty::Text)];
+    leaf.prop_recursive(3, 8, 2, |t| {
+        let fields = prop::collection::vec(t.clone(), 0..3).prop_map(|ts| {
+            let labels = ["a", "b", "c"].iter().map(|l| Label::from(*l));
+            Ty::Record(labels.zip(ts).collect())
+        });
+        prop_oneof![
+            t.clone().prop_map(|t| Ty::List(Box::new(t))),
+            t.clone().prop_map(|t| Ty::Optional(Box::new(t))),
+            fields,
+            (t.clone(), t).prop_map(|(a, b)| Ty::Fun(Box::new(a), Box::new(b))),
+        ]
+    })
+}
+
+fn ty_expr(ty: &Ty) -> ArbExpr {
+    use ExprF::*;
+    rc(match ty {
+        Ty::Bool => Builtin(Builtin::Bool),
+        Ty::Natural => Builtin(Builtin::Natural),
+        Ty::Text => Builtin(Builtin::Text),
+        Ty::List(t) => App(rc(Builtin(Builtin::List)), ty_expr(t)),
+        Ty::Optional(t) => App(rc(Builtin(Builtin::Optional)), ty_expr(t)),
+        Ty::Record(fields) => RecordType(
+            fields
+                .iter()
+                .map(|(l, t)| (l.clone(), ty_expr(t)))
+                .collect(),
+        ),
+        Ty::Fun(a, b) => Pi("_".into(), ty_expr(a), ty_expr(b)),
+    })
+}
+
+fn app(f: Builtin, args: Vec<ArbExpr>) -> ArbExpr {
+    args.into_iter()
+        .fold(rc(ExprF::Builtin(f)), |f, a| rc(ExprF::App(f, a)))
+}
+
+/// Closed, well-typed expressions of arbitrary types. They use binders,
+/// variables and beta-redexes, so that normalization has work to do.
+pub fn well_typed_expr() -> BoxedStrategy<ArbExpr> {
+    any_ty()
+        .prop_flat_map(|t| of_type(&t, &Ctx::new(), 2))
+        .boxed()
+}
+
+fn of_type(ty: &Ty, ctx: &Ctx, depth: u32) -> BoxedStrategy<ArbExpr> {
+    use ExprF::*;
+    let mut choices = vec![literal(ty, ctx, depth)];
+    for (i, (l, t)) in ctx.iter().enumerate() {
+        if t == ty {
+            let n = ctx[i + 1..].iter().filter(|(l2, _)| l2 == l).count();
+            choices.push(Just(rc(Var(V(l.clone(), n)))).boxed());
+        }
+    }
+    if depth == 0 {
+        return Union::new(choices).boxed();
+    }
+    let d = depth - 1;
+    let sub = |t: &Ty| of_type(t, ctx, d);
+    choices.push(
+        (sub(&Ty::Bool), sub(ty), sub(ty))
+            .prop_map(|(c, t, f)| rc(BoolIf(c, t, f)))
+            .boxed(),
+    );
+    {
+        let (ty, ctx) = (ty.clone(), ctx.clone());
+        let binder = (any_ty(), binder_label());
+        choices.push(
+            binder
+                .prop_flat_map(move |(a, l)| {
+                    let mut inner = ctx.clone();
+                    inner.push((l.clone(), a.clone()));
+                    let a_expr = ty_expr(&a);
+                    let value = of_type(&a, &ctx, d);
+                    let body = of_type(&ty, &inner, d);
+                    (any::<bool>(), value, body).prop_map(
+                        move |(redex, v, b)| {
+                            let l = l.clone();
+                            if redex {
+                                let f = rc(Lam(l, a_expr.clone(), b));
+                                rc(App(f, v))
+                            } else {
+                                rc(Let(l, None, v, b))
+                            }
+                        },
+                    )
+                })
+                .boxed(),
+        );
+    }
+    {
+        // Selects a field from a record literal
+        let field = Label::from("a");
+        let other = Label::from("b");
+        let (ty, ctx) = (ty.clone(), ctx.clone());
+        choices.push(
+            any_ty()
+                .prop_flat_map(move |b| {
+                    (of_type(&ty, &ctx, d), of_type(&b, &ctx, d))
+                })
+                .prop_map(move |(x, y)| {
+                    let fields = vec![(field.clone(), x), (other.clone(), y)];
+                    rc(Field(
+                        rc(RecordLit(fields.into_iter().collect())),
+                        field.clone(),
+                    ))
+                })
+                .boxed(),
+        );
+    }
+    let op = |op: BinOp, t: &Ty| {
+        (sub(t), sub(t))
+            .prop_map(move |(a, b)| rc(BinOp(op, a, b)))
+            .boxed()
+    };
+    match ty {
+        Ty::Bool => {
+            choices.push(op(BinOp::BoolAnd, &Ty::Bool));
+            choices.push(op(BinOp::BoolOr, &Ty::Bool));
+            choices.push(op(BinOp::BoolEQ, &Ty::Bool));
+            choices.push(op(BinOp::BoolNE, &Ty::Bool));
+            choices.push(
+                sub(&Ty::Natural)
+                    .prop_map(|n| app(Builtin::NaturalEven, vec![n]))
+                    .boxed(),
+            );
+            choices.push(
+                sub(&Ty::Natural)
+                    .prop_map(|n| app(Builtin::NaturalIsZero, vec![n]))
+                    .boxed(),
+            );
+        }
+        Ty::Natural => {
+            choices.push(op(BinOp::NaturalPlus, &Ty::Natural));
+            choices.push(op(BinOp::NaturalTimes, &Ty::Natural));
+            let list = Ty::List(Box::new(Ty::Natural));
+            choices.push(
+                sub(&list)
+                    .prop_map(|xs| {
+                        let t = ty_expr(&Ty::Natural);
+                        app(Builtin::ListLength, vec![t, xs])
+                    })
+                    .boxed(),
+            );
+        }
+        Ty::Text => {
+            choices.push(op(BinOp::TextAppend, &Ty::Text));
+            choices.push(
+                sub(&Ty::Natural)
+                    .prop_map(|n| app(Builtin::NaturalShow, vec![n]))
+                    .boxed(),
+            );
+            choices.push(
+                ("[a-z ]{0,3}", sub(&Ty::Text), "[a-z ]{0,3}")
+                    .prop_map(|(head, x, tail)| {
+                        let t = InterpolatedText::from((head, vec![(x, tail)]));
+                        rc(TextLit(t))
+                    })
+                    .boxed(),
+            );
+        }
+        Ty::List(t) => {
+            choices.push(op(BinOp::ListAppend, ty));
+            let t = ty_expr(t);
+            choices.push(
+                sub(ty)
+                    .prop_map(move |xs| {
+                        app(Builtin::ListReverse, vec![t.clone(), xs])
+                    })
+                    .boxed(),
+            );
+        }
+        Ty::Optional(t) => {
+            let list = Ty::List(t.clone());
+            let t = ty_expr(t);
+            choices.push(
+                sub(&list)
+                    .prop_map(move |xs| {
+                        app(Builtin::ListHead, vec![t.clone(), xs])
+                    })
+                    .boxed(),
+            );
+        }
+        Ty::Record(_) | Ty::Fun(_, _) => {}
+    }
+    Union::new(choices).boxed()
+}
+
+fn binder_label() -> impl Strategy<Value = Label> {
+    prop::sample::select(vec!["x", "y"]).prop_map(Label::from)
+}
+
+/// Values of type `ty` built from literals and lambdas.
+fn literal(ty: &Ty, ctx: &Ctx, depth: u32) -> BoxedStrategy<ArbExpr> {
+    use ExprF::*;
+    match ty {
+        Ty::Bool => any::<bool>().prop_map(|b| rc(BoolLit(b))).boxed(),
+        Ty::Natural => {
+            (0..100u64).prop_map(|n| rc(NaturalLit(n.into()))).boxed()
+        }
+        Ty::Text => "[a-z ]{0,4}"
+            .prop_map(|s| rc(TextLit(InterpolatedText::from(s))))
+            .boxed(),
+        Ty::List(t) => {
+            let empty = Just(rc(EmptyListLit(ty_expr(t))));
+            let elems = prop::collection::vec(of_type(t, ctx, depth), 1..3);
+            prop_oneof![empty, elems.prop_map(|xs| rc(NEListLit(xs)))].boxed()
+        }
+        Ty::Optional(t) => {
+            let none = Just(app(Builtin::OptionalNone, vec![ty_expr(t)]));
+            let some = of_type(t, ctx, depth).prop_map(|x| rc(SomeLit(x)));
+            prop_oneof![none, some].boxed()
+        }
+        Ty::Record(fields) => {
+            let labels: Vec<_> =
+                fields.iter().map(|(l, _)| l.clone()).collect();
+            let values: Vec<_> =
+                fields.iter().map(|(_, t)| of_type(t, ctx, depth)).collect();
+            values
+                .prop_map(move |vs| {
+                    rc(RecordLit(labels.iter().cloned().zip(vs).collect()))
+                })
+                .boxed()
+        }
+        Ty::Fun(a, b) => {
+            let (a, b, ctx) = ((**a).clone(), (**b).clone(), ctx.clone());
+            binder_label()
+                .prop_flat_map(move |l| {
+                    let mut inner = ctx.clone();
+                    inner.push((l.clone(), a.clone()));
+                    let a_expr = ty_expr(&a);
+                    of_type(&b, &inner, depth).prop_map(move |body| {
+                        rc(Lam(l.clone(), a_expr.clone(), body))
+                    })
+                })
+                .boxed()
+        }
+    }
+}
@@ -1,6 +1,5 @@
 use std::collections::HashMap;
-use std::rc::Rc;
-
+use dhall_syntax::sync::Rc;
 use dhall_syntax::{Label, V};
 
 use crate::core::thunk::Thunk;
@@ -15,8 +14,18 @@ pub enum CtxItem<T> {
     Replaced(Thunk, T),
 }
 
-#[derive(Debug, Clone)]
-pub struct Context<T>(Rc<Vec<(Label, CtxItem<T>)>>);
+/// A persistent cons-list of context items, most recent binder first.
+/// Inserting an item is O(1) and shares the rest of the list with the
+/// original context.
+#[derive(Debug)]
+pub struct Context<T>(Option<Rc<ContextNode<T>>>);
+
+#[derive(Debug)]
+struct ContextNode<T> {
+    label: Label,
+    item: CtxItem<T>,
+    rest: Context<T>,
+}
 
 #[derive(Debug, Clone)]
 pub struct NormalizationContext(Context<()>);
@@ -26,23 +35,32 @@ pub struct TypecheckContext(Context<Type>);
 
 impl<T> Context<T> {
     pub fn new() -> Self {
-        Context(Rc::new(Vec::new()))
+        Context(None)
+    }
+    fn cons(&self, label: Label, item: CtxItem<T>) -> Self {
+        Context(Some(Rc::new(ContextNode {
+            label,
+            item,
+            rest: self.clone(),
+        })))
+    }
+    /// Iterates over the items, starting from the most recent one.
+    fn iter(&self) -> impl Iterator<Item = (&Label, &CtxItem<T>)> {
+        let mut ctx = self;
+        std::iter::from_fn(move || {
+            let node = ctx.0.as_ref()?;
+            ctx = &node.rest;
+            Some((&node.label, &node.item))
+        })
     }
     pub fn insert_kept(&self, x: &Label, t: T) -> Self
     where
-        T: Shift + Clone,
+        T: Shift,
     {
-        let mut vec = self.0.as_ref().clone();
-        vec.push((x.clone(), CtxItem::Kept(x.into(), t.under_binder(x))));
-        Context(Rc::new(vec))
+        self.cons(x.clone(), CtxItem::Kept(x.into(), t.under_binder(x)))
     }
-    pub fn insert_replaced(&self, x: &Label, th: Thunk, t: T) -> Self
-    where
-        T: Clone,
-    {
-        let mut vec = self.0.as_ref().clone();
-        vec.push((x.clone(), CtxItem::Replaced(th, t)));
-        Context(Rc::new(vec))
+    pub fn insert_replaced(&self, x: &Label, th: Thunk, t: T) -> Self {
+        self.cons(x.clone(), CtxItem::Replaced(th, t))
     }
     pub fn lookup(&self, var: &V<Label>) -> Result<CtxItem<T>, V<Label>>
     where
@@ -50,7 +68,7 @@ impl<T> Context<T> {
     {
         let mut var = var.clone();
         let mut shift_map: HashMap<Label, _> = HashMap::new();
-        for (l, i) in self.0.iter().rev() {
+        for (l, i) in self.iter() {
             match var.over_binder(l) {
                 None => return Ok(i.under_multiple_binders(&shift_map)),
                 Some(newvar) => var = newvar,
@@ -65,33 +83,29 @@ impl<T> Context<T> {
     /// Given a var that makes sense in the current context, map the given function in such a way
     /// that the passed variable always makes sense in the context of the passed item.
     /// Once we pass the variable definition, the variable doesn't make sense anymore so we just
-    /// copy the remaining items.
+    /// share the remaining items.
     fn do_with_var<E>(
         &self,
         var: &AlphaVar,
         mut f: impl FnMut(&AlphaVar, &CtxItem<T>) -> Result<CtxItem<T>, E>,
-    ) -> Result<Self, E>
-    where
-        T: Clone,
-    {
-        let mut vec = Vec::new();
-        vec.reserve(self.0.len());
+    ) -> Result<Self, E> {
+        let mut items = Vec::new();
         let mut var = var.clone();
-        let mut iter = self.0.iter().rev();
-        for (l, i) in iter.by_ref() {
-            vec.push((l.clone(), f(&var, i)?));
-            if let CtxItem::Kept(_, _) = i {
-                match var.over_binder(l) {
+        let mut ctx = self;
+        while let Some(node) = &ctx.0 {
+            items.push((node.label.clone(), f(&var, &node.item)?));
+            ctx = &node.rest;
+            if let CtxItem::Kept(_, _) = node.item {
+                match var.over_binder(&node.label) {
                     None => break,
                     Some(newvar) => var = newvar,
                 };
             }
         }
-        for (l, i) in iter {
-            vec.push((l.clone(), (*i).clone()));
-        }
-        vec.reverse();
-        Ok(Context(Rc::new(vec)))
+        Ok(items
+            .into_iter()
+            .rev()
+            .fold(ctx.clone(), |rest, (l, i)| rest.cons(l, i)))
     }
     fn shift(&self, delta: isize, var: &AlphaVar) -> Option<Self>
     where
@@ -108,6 +122,26 @@ impl<T> Context<T> {
     }
 }
 
+impl<T> Clone for Context<T> {
+    fn clone(&self) -> Self {
+        Context(self.0.clone())
+    }
+}
+
+impl<T> Drop for Context<T> {
+    // Drop the list iteratively; the default recursive drop would overflow
+    // the stack on long contexts.
+    fn drop(&mut self) {
+        let mut next = self.0.take();
+        while let Some(rc) = next {
+            match Rc::try_unwrap(rc) {
+                Ok(mut node) => next = node.rest.0.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
 impl NormalizationContext {
     pub fn new() -> Self {
         NormalizationContext(Context::new())
@@ -0,0 +1,192 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::core::thunk::{Thunk, TypeThunk, WeakThunk};
+use crate::core::value::Value;
+use crate::phase::Typed;
+
+/// Hash-consing of record and union types.
+///
+/// Configs tend to repeat the same large record types many times over. Record
+/// and union types are interned as they get built by the typechecker, keyed
+/// by a structural hash of their value, so that equal types end up sharing a
+/// single thunk. This saves memory, and makes comparing them a pointer
+/// comparison.
+///
+/// Entries are weak, so interning does not keep types alive.
+struct TypeTable {
+    types: HashMap<u64, Vec<WeakThunk>>,
+    /// Dead entries get pruned when the table grows past this size.
+    prune_at: usize,
+}
+
+impl TypeTable {
+    fn new() -> Self {
+        TypeTable {
+            types: HashMap::new(),
+            prune_at: 1024,
+        }
+    }
+
+    /// Returns the shared thunk equal to `th`, or registers `th` as such.
+    fn intern(&mut self, key: u64, th: &Thunk) -> Option<Thunk> {
+        if let Some(candidates) = self.types.get(&key) {
+            let shared = candidates
+                .iter()
+                .filter_map(WeakThunk::upgrade)
+                .find(|shared| shared == th);
+            if shared.is_some() {
+                return shared;
+            }
+        }
+        if self.types.len() >= self.prune_at {
+            self.types.retain(|_, ws| {
+                ws.retain(|w| w.upgrade().is_some());
+                !ws.is_empty()
+            });
+            self.prune_at = std::cmp::max(1024, 2 * self.types.len());
+        }
+        self.types.entry(key).or_default().push(th.downgrade());
+        None
+    }
+}
+
+thread_local! {
+    static TYPES: RefCell<TypeTable> = RefCell::new(TypeTable::new());
+}
+
+/// Replaces a record or union type that was just built with an equal one
+/// that was interned before, if any. Only types without variables are
+/// interned.
+pub(crate) fn intern_type(t: Typed) -> Typed {
+    let (th, kind) = match t {
+        Typed::Typed(th, kind) => (th, kind),
+        t => return t,
+    };
+    if !is_closed(&th.as_value()) {
+        return Typed::Typed(th, kind);
+    }
+    let key = {
+        let mut hasher = DefaultHasher::new();
+        hash_value(&th.as_value(), &mut hasher);
+        hasher.finish()
+    };
+    match TYPES.with(|types| types.borrow_mut().intern(key, &th)) {
+        Some(shared) => Typed::Typed(shared, kind),
+        None => Typed::Typed(th, kind),
+    }
+}
+
+/// Hashes a value so that equal values get equal hashes. Equality is up to
+/// alpha-equivalence, so binder names and variables are left out, as are
+/// the contents of values that are rare in types. Collisions are resolved by
+/// comparing values.
+fn hash_value(v: &Value, state: &mut impl Hasher) {
+    std::mem::discriminant(v).hash(state);
+    match v {
+        Value::Const(c) => c.hash(state),
+        Value::AppliedBuiltin(b, args) => {
+            b.hash(state);
+            for x in args {
+                hash_value(&x.normalize_nf(), state);
+            }
+        }
+        Value::Pi(_, t, e) => {
+            hash_type(t, state);
+            hash_type(e, state);
+        }
+        Value::RecordType(kts) => {
+            // Combine the fields in a way that does not depend on the
+            // iteration order of the map
+            let fields = kts.iter().fold(0u64, |acc, (k, t)| {
+                let mut field = DefaultHasher::new();
+                k.as_ref().hash(&mut field);
+                hash_type(t, &mut field);
+                acc.wrapping_add(field.finish())
+            });
+            fields.hash(state);
+        }
+        Value::UnionType(kts) => {
+            let alternatives = kts.iter().fold(0u64, |acc, (k, t)| {
+                let mut alternative = DefaultHasher::new();
+                k.as_ref().hash(&mut alternative);
+                if let Some(t) = t {
+                    hash_type(t, &mut alternative);
+                }
+                acc.wrapping_add(alternative.finish())
+            });
+            alternatives.hash(state);
+        }
+        _ => {}
+    }
+}
+
+fn hash_type(t: &TypeThunk, state: &mut impl Hasher) {
+    hash_value(&t.to_thunk().normalize_nf(), state)
+}
+
+/// Whether a value mentions no variable, bound or free. Equality is up to
+/// alpha-equivalence, so sharing a type that mentions one could make it
+/// print with the names of another binder, e.g. `{ f : a }` in place of
+/// `{ f : b }`. Values that are rare in types count as not closed.
+fn is_closed(v: &Value) -> bool {
+    match v {
+        Value::Const(_) => true,
+        Value::AppliedBuiltin(_, args) => {
+            args.iter().all(|x| is_closed(&x.normalize_nf()))
+        }
+        Value::Pi(_, t, e) => is_closed_type(t) && is_closed_type(e),
+        Value::RecordType(kts) => kts.values().all(is_closed_type),
+        Value::UnionType(kts) => kts.values().flatten().all(is_closed_type),
+        _ => false,
+    }
+}
+
+fn is_closed_type(t: &TypeThunk) -> bool {
+    is_closed(&t.to_thunk().normalize_nf())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::phase::{Parsed, Type};
+
+    fn get_type(s: &str) -> Type {
+        let typed = Parsed::parse_str(s)
+            .unwrap()
+            .skip_resolve()
+            .unwrap()
+            .typecheck()
+            .unwrap();
+        typed.get_type().unwrap().into_owned()
+    }
+
+    #[test]
+    fn equal_record_types_are_shared() {
+        let t1 = get_type("{ x = 1, y = [\"a\"] }");
+        let t2 = get_type("{ y = [\"b\", \"c\"], x = 0 }");
+        assert!(t1.as_thunk().unwrap().ptr_eq(t2.as_thunk().unwrap()));
+        assert_eq!(t1, t2);
+    }
+
+    #[test]
+    fn types_with_variables_keep_their_names() {
+        // Free in the record type
+        let t1 = get_type("λ(a : Type) → λ(r : { f : a }) → r");
+        let t2 = get_type("λ(b : Type) → λ(r : { f : b }) → r");
+        assert_eq!(t1, t2);
+        assert_eq!(
+            t2.to_string(),
+            "∀(b : Type) → ∀(r : { f : b }) → { f : b }"
+        );
+        // Bound in the record type
+        let t1 = get_type("λ(r : { g : ∀(x : Type) → x }) → r");
+        let t2 = get_type("λ(r : { g : ∀(y : Type) → y }) → r");
+        assert_eq!(t1, t2);
+        assert_eq!(
+            t2.to_string(),
+            "∀(r : { g : ∀(y : Type) → y }) → { g : ∀(y : Type) → y }"
+        );
+    }
+}
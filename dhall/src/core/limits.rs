@@ -0,0 +1,200 @@
+use std::cell::{Cell, RefCell};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::error::{Error, ResourceLimit};
+
+/// Bounds on the work normalization may do, to protect against adversarial
+/// input. `None` means unbounded, which is the default for every limit.
+///
+/// Normalization is lazy and can happen anywhere from typechecking to
+/// deserialization, so the limits are installed for the whole duration of a
+/// call (see
+/// [dhall::de::from_str_with_limits][crate::de::from_str_with_limits]),
+/// including on the threads that load imports in parallel. The step budget
+/// is shared by all of them.
+///
+/// Exceeding a limit unwinds the stack of the normalizer, so the limits
+/// require `panic = "unwind"`, the default. When panics abort, exceeding a
+/// limit aborts the process.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EvalLimits {
+    /// Maximum number of normalization steps (function applications and
+    /// expression layers evaluated).
+    pub max_steps: Option<u64>,
+    /// Maximum length of any list built during normalization.
+    pub max_list_length: Option<usize>,
+    /// Maximum length in bytes of any text built during normalization.
+    pub max_text_length: Option<usize>,
+    /// Maximum nesting of normalization calls. This guards against stack
+    /// overflows.
+    pub max_depth: Option<usize>,
+}
+
+/// The limits of one evaluation, and the number of steps it took so far.
+/// Shared by all the threads that take part in the evaluation, so that work
+/// done in parallel counts towards the same budget.
+#[derive(Debug)]
+struct Budget {
+    limits: EvalLimits,
+    steps: AtomicU64,
+}
+
+thread_local! {
+    static BUDGET: RefCell<Option<Arc<Budget>>> = RefCell::new(None);
+    static DEPTH: Cell<usize> = Cell::new(0);
+}
+
+/// The evaluation the current thread takes part in, if it has limits. Other
+/// threads can join it with [Evaluation::join].
+#[derive(Debug, Clone)]
+pub(crate) struct Evaluation(Option<Arc<Budget>>);
+
+impl Evaluation {
+    #[cfg(feature = "sync")]
+    pub(crate) fn current() -> Self {
+        Evaluation(BUDGET.with(|b| b.borrow().clone()))
+    }
+
+    /// Runs `f` as part of this evaluation: its limits apply, and its steps
+    /// count towards the same budget. The previous evaluation of the current
+    /// thread is restored afterwards, even on unwind.
+    pub(crate) fn join<T>(&self, f: impl FnOnce() -> T) -> T {
+        struct Restore(Option<Arc<Budget>>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                BUDGET.with(|b| *b.borrow_mut() = self.0.take());
+            }
+        }
+        let _restore = Restore(BUDGET.with(|b| b.replace(self.0.clone())));
+        f()
+    }
+}
+
+impl EvalLimits {
+    /// Runs `f` with these limits, turning an exceeded limit into an error.
+    /// This bounds the evaluation done by the phases of
+    /// [dhall::pipeline][crate::pipeline]. Normalization is lazy, so the
+    /// result should be forced inside `f`.
+    ///
+    /// ```edition2018
+    /// # fn main() -> dhall::error::Result<()> {
+    /// use dhall::de::EvalLimits;
+    /// use dhall::error::{Error, ResourceLimit};
+    /// use dhall::pipeline::Parsed;
+    ///
+    /// let limits = EvalLimits {
+    ///     max_steps: Some(1000),
+    ///     ..EvalLimits::default()
+    /// };
+    /// let s = "Natural/fold 1000000000 Natural (λ(x : Natural) → x + 1) 0";
+    /// let result = limits.run(|| {
+    ///     let typed = Parsed::parse_str(s)?.skip_resolve()?.typecheck()?;
+    ///     Ok(typed.normalize().to_expr())
+    /// });
+    /// match result {
+    ///     Err(Error::ResourceLimit(ResourceLimit::Steps)) => {}
+    ///     _ => panic!("expected the step limit to be hit"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run<T>(
+        self,
+        f: impl FnOnce() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let evaluation = Evaluation(Some(Arc::new(Budget {
+            limits: self,
+            steps: AtomicU64::new(0),
+        })));
+        // Normalization has no way to return errors, so exceeding a limit
+        // unwinds the stack up to here. Thunks only store the result of an
+        // evaluation once it has completed, and their locks ignore poisoning,
+        // so thunks shared with the caller stay usable.
+        match catch_unwind(AssertUnwindSafe(|| evaluation.join(f))) {
+            Ok(x) => x,
+            Err(payload) => match payload.downcast::<ResourceLimit>() {
+                Ok(limit) => Err(Error::ResourceLimit(*limit)),
+                Err(payload) => resume_unwind(payload),
+            },
+        }
+    }
+}
+
+/// The limits of the evaluation the current thread takes part in.
+fn current_limits() -> EvalLimits {
+    BUDGET.with(|b| match &*b.borrow() {
+        Some(budget) => budget.limits,
+        None => EvalLimits::default(),
+    })
+}
+
+#[cfg(not(panic_abort))]
+fn exceeded(limit: ResourceLimit) -> ! {
+    // `resume_unwind` does not invoke the panic hook, so nothing gets printed.
+    resume_unwind(Box::new(limit))
+}
+
+#[cfg(panic_abort)]
+fn exceeded(limit: ResourceLimit) -> ! {
+    // Unwinding would abort without a word; at least say why.
+    panic!(
+        "{}; evaluation limits cannot be recovered from when panics abort",
+        limit
+    )
+}
+
+/// Counts one normalization step.
+pub(crate) fn step() {
+    let exhausted = BUDGET.with(|b| match &*b.borrow() {
+        Some(budget) => match budget.limits.max_steps {
+            Some(max) => budget.steps.fetch_add(1, Ordering::Relaxed) >= max,
+            None => false,
+        },
+        None => false,
+    });
+    if exhausted {
+        exceeded(ResourceLimit::Steps)
+    }
+}
+
+pub(crate) fn check_list_length(len: usize) {
+    match current_limits().max_list_length {
+        Some(max) if len > max => exceeded(ResourceLimit::ListLength),
+        _ => {}
+    }
+}
+
+pub(crate) fn check_text_length(len: usize) {
+    match current_limits().max_text_length {
+        Some(max) if len > max => exceeded(ResourceLimit::TextLength),
+        _ => {}
+    }
+}
+
+/// Tracks the nesting of normalization calls. Keep it alive for the duration
+/// of the call.
+pub(crate) struct DepthGuard(());
+
+impl DepthGuard {
+    pub(crate) fn enter() -> Self {
+        let depth = DEPTH.with(|d| {
+            let depth = d.get() + 1;
+            d.set(depth);
+            depth
+        });
+        // Construct the guard first so the depth gets decremented on unwind
+        let guard = DepthGuard(());
+        match current_limits().max_depth {
+            Some(max) if depth > max => exceeded(ResourceLimit::Depth),
+            _ => guard,
+        }
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        DEPTH.with(|d| d.set(d.get() - 1));
+    }
+}
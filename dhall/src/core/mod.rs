@@ -1,4 +1,6 @@
 pub(crate) mod context;
+pub(crate) mod intern;
+pub(crate) mod limits;
 pub(crate) mod thunk;
 pub(crate) mod value;
 pub(crate) mod var;
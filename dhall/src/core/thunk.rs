@@ -1,6 +1,7 @@
-use std::cell::{Ref, RefCell};
-use std::rc::Rc;
+use std::ops::Deref;
 
+use dhall_syntax::stack::ensure_sufficient_stack;
+use dhall_syntax::sync::{Rc, Weak};
 use dhall_syntax::{ExprF, X};
 
 use crate::core::context::NormalizationContext;
@@ -11,6 +12,54 @@ use crate::phase::normalize::{
 };
 use crate::phase::{Type, Typed};
 
+/// The interior-mutable cell that holds a thunk's contents: a `RefCell`, or
+/// an `RwLock` with the `sync` feature so that thunks can be shared between
+/// threads.
+#[cfg(not(feature = "sync"))]
+mod cell {
+    use std::cell::{Ref, RefCell, RefMut};
+
+    pub type Cell<T> = RefCell<T>;
+    pub type ReadGuard<'a, T> = Ref<'a, T>;
+
+    pub fn new<T>(x: T) -> Cell<T> {
+        RefCell::new(x)
+    }
+    pub fn read<T>(c: &Cell<T>) -> Ref<T> {
+        c.borrow()
+    }
+    pub fn write<T>(c: &Cell<T>) -> RefMut<T> {
+        c.borrow_mut()
+    }
+    pub fn get_mut<T>(c: &mut Cell<T>) -> &mut T {
+        c.get_mut()
+    }
+}
+
+#[cfg(feature = "sync")]
+mod cell {
+    use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+    pub type Cell<T> = RwLock<T>;
+    pub type ReadGuard<'a, T> = RwLockReadGuard<'a, T>;
+
+    // Locks get poisoned when an exceeded evaluation limit unwinds through a
+    // normalization. This is harmless: a thunk only stores the result of a
+    // normalization once it has completed, so its contents stay consistent.
+    pub fn new<T>(x: T) -> Cell<T> {
+        RwLock::new(x)
+    }
+    pub fn read<T>(c: &Cell<T>) -> RwLockReadGuard<T> {
+        c.read().unwrap_or_else(PoisonError::into_inner)
+    }
+    pub fn write<T>(c: &Cell<T>) -> RwLockWriteGuard<T> {
+        c.write().unwrap_or_else(PoisonError::into_inner)
+    }
+    pub fn get_mut<T>(c: &mut Cell<T>) -> &mut T {
+        c.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum Marker {
     /// Weak Head Normal Form, i.e. subexpressions may not be normalized
@@ -35,9 +84,16 @@ enum ThunkInternal {
 
 /// Stores a possibly unevaluated value. Gets (partially) normalized on-demand,
 /// sharing computation automatically.
-/// Uses a RefCell to share computation.
+/// Uses a RefCell (or a lock with the `sync` feature) to share computation.
 #[derive(Debug, Clone)]
-pub struct Thunk(Rc<RefCell<ThunkInternal>>);
+pub struct Thunk(Rc<cell::Cell<ThunkInternal>>);
+
+/// A non-owning reference to a thunk.
+#[derive(Debug, Clone)]
+pub struct WeakThunk(Weak<cell::Cell<ThunkInternal>>);
+
+/// A borrow of the value stored in a thunk, which is at least in WHNF.
+pub struct ValueRef<'a>(cell::ReadGuard<'a, ThunkInternal>);
 
 /// A thunk in type position. Can optionally store a Type from the typechecking phase to preserve
 /// type information through the normalization phase.
@@ -46,20 +102,21 @@ pub struct TypeThunk(Typed);
 
 impl ThunkInternal {
     fn into_thunk(self) -> Thunk {
-        Thunk(Rc::new(RefCell::new(self)))
+        Thunk(Rc::new(cell::new(self)))
     }
 
     fn normalize_whnf(&mut self) {
         match self {
             ThunkInternal::Unnormalized(ctx, e) => {
-                *self = ThunkInternal::Value(
-                    WHNF,
-                    normalize_whnf(ctx.clone(), e.clone()),
-                )
+                let v = ensure_sufficient_stack(|| {
+                    normalize_whnf(ctx.clone(), e.clone())
+                });
+                *self = ThunkInternal::Value(WHNF, v)
             }
             ThunkInternal::PartialExpr(e) => {
-                *self =
-                    ThunkInternal::Value(WHNF, normalize_one_layer(e.clone()))
+                let v =
+                    ensure_sufficient_stack(|| normalize_one_layer(e.clone()));
+                *self = ThunkInternal::Value(WHNF, v)
             }
             // Already at least in WHNF
             ThunkInternal::Value(_, _) => {}
@@ -74,7 +131,7 @@ impl ThunkInternal {
                 self.normalize_nf();
             }
             ThunkInternal::Value(m @ WHNF, v) => {
-                v.normalize_mut();
+                ensure_sufficient_stack(|| v.normalize_mut());
                 *m = NF;
             }
             // Already in NF
@@ -124,19 +181,19 @@ impl Thunk {
     pub fn normalize_mut(&mut self) {
         match Rc::get_mut(&mut self.0) {
             // Mutate directly if sole owner
-            Some(refcell) => RefCell::get_mut(refcell).normalize_nf(),
-            // Otherwise mutate through the refcell
-            None => self.0.borrow_mut().normalize_nf(),
+            Some(c) => cell::get_mut(c).normalize_nf(),
+            // Otherwise mutate through the cell
+            None => cell::write(&self.0).normalize_nf(),
         }
     }
 
     fn do_normalize_whnf(&self) {
-        let borrow = self.0.borrow();
+        let borrow = cell::read(&self.0);
         match &*borrow {
             ThunkInternal::Unnormalized(_, _)
             | ThunkInternal::PartialExpr(_) => {
                 drop(borrow);
-                self.0.borrow_mut().normalize_whnf();
+                cell::write(&self.0).normalize_whnf();
             }
             // Already at least in WHNF
             ThunkInternal::Value(_, _) => {}
@@ -144,13 +201,13 @@ impl Thunk {
     }
 
     fn do_normalize_nf(&self) {
-        let borrow = self.0.borrow();
+        let borrow = cell::read(&self.0);
         match &*borrow {
             ThunkInternal::Unnormalized(_, _)
             | ThunkInternal::PartialExpr(_)
             | ThunkInternal::Value(WHNF, _) => {
                 drop(borrow);
-                self.0.borrow_mut().normalize_nf();
+                cell::write(&self.0).normalize_nf();
             }
             // Already in NF
             ThunkInternal::Value(NF, _) => {}
@@ -158,17 +215,20 @@ impl Thunk {
     }
 
     // WARNING: avoid normalizing any thunk while holding on to this ref
-    // or you could run into BorrowMut panics
-    pub fn normalize_nf(&self) -> Ref<Value> {
+    // or you could run into BorrowMut panics (or deadlocks with `sync`)
+    pub fn normalize_nf(&self) -> ValueRef {
         self.do_normalize_nf();
-        Ref::map(self.0.borrow(), ThunkInternal::as_nf)
+        let borrow = cell::read(&self.0);
+        // Check the invariant
+        borrow.as_nf();
+        ValueRef(borrow)
     }
 
     // WARNING: avoid normalizing any thunk while holding on to this ref
-    // or you could run into BorrowMut panics
-    pub fn as_value(&self) -> Ref<Value> {
+    // or you could run into BorrowMut panics (or deadlocks with `sync`)
+    pub fn as_value(&self) -> ValueRef {
         self.do_normalize_whnf();
-        Ref::map(self.0.borrow(), ThunkInternal::as_whnf)
+        ValueRef(cell::read(&self.0))
     }
 
     pub fn to_value(&self) -> Value {
@@ -186,6 +246,21 @@ impl Thunk {
     pub fn app_thunk(&self, th: Thunk) -> Value {
         apply_any(self.clone(), th)
     }
+
+    pub fn downgrade(&self) -> WeakThunk {
+        WeakThunk(Rc::downgrade(&self.0))
+    }
+
+    /// Whether the two thunks share the same allocation.
+    pub fn ptr_eq(&self, other: &Thunk) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl WeakThunk {
+    pub fn upgrade(&self) -> Option<Thunk> {
+        Some(Thunk(self.0.upgrade()?))
+    }
 }
 
 impl TypeThunk {
@@ -226,9 +301,30 @@ impl TypeThunk {
     }
 }
 
+impl Drop for Thunk {
+    fn drop(&mut self) {
+        // Drop deep chains of thunks without overflowing the stack; see
+        // `SubExpr`'s `Drop` impl.
+        if let Some(c) = Rc::get_mut(&mut self.0) {
+            let dummy = ThunkInternal::Value(NF, Value::BoolLit(false));
+            let th = std::mem::replace(cell::get_mut(c), dummy);
+            ensure_sufficient_stack(move || drop(th));
+        }
+    }
+}
+
+impl<'a> Deref for ValueRef<'a> {
+    type Target = Value;
+    fn deref(&self) -> &Value {
+        self.0.as_whnf()
+    }
+}
+
 impl Shift for Thunk {
     fn shift(&self, delta: isize, var: &AlphaVar) -> Option<Self> {
-        Some(self.0.borrow().shift(delta, var)?.into_thunk())
+        let th =
+            ensure_sufficient_stack(|| cell::read(&self.0).shift(delta, var));
+        Some(th?.into_thunk())
     }
 }
 
@@ -260,7 +356,8 @@ impl Shift for TypeThunk {
 
 impl Subst<Typed> for Thunk {
     fn subst_shift(&self, var: &AlphaVar, val: &Typed) -> Self {
-        self.0.borrow().subst_shift(var, val).into_thunk()
+        ensure_sufficient_stack(|| cell::read(&self.0).subst_shift(var, val))
+            .into_thunk()
     }
 }
 
@@ -299,14 +396,14 @@ impl Subst<Typed> for TypeThunk {
 
 impl std::cmp::PartialEq for Thunk {
     fn eq(&self, other: &Self) -> bool {
-        *self.as_value() == *other.as_value()
+        self.ptr_eq(other) || *self.as_value() == *other.as_value()
     }
 }
 impl std::cmp::Eq for Thunk {}
 
 impl std::cmp::PartialEq for TypeThunk {
     fn eq(&self, other: &Self) -> bool {
-        self.to_value() == other.to_value()
+        self.0 == other.0
     }
 }
 impl std::cmp::Eq for TypeThunk {}
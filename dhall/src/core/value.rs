@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use dhall_proc_macros as dhall;
+use dhall_syntax::stack::ensure_sufficient_stack;
 use dhall_syntax::{
     rc, Builtin, Const, ExprF, Integer, InterpolatedTextContents, Label,
     NaiveDouble, Natural, X,
@@ -73,140 +74,142 @@ impl Value {
     /// Convert the value to a fully normalized syntactic expression. Also alpha-normalize
     /// if alpha is `true`
     pub fn normalize_to_expr_maybe_alpha(&self, alpha: bool) -> OutputSubExpr {
-        match self {
-            Value::Lam(x, t, e) => rc(ExprF::Lam(
-                x.to_label_maybe_alpha(alpha),
-                t.normalize_to_expr_maybe_alpha(alpha),
-                e.normalize_to_expr_maybe_alpha(alpha),
-            )),
-            Value::AppliedBuiltin(b, args) => {
-                let mut e = rc(ExprF::Builtin(*b));
-                for v in args {
-                    e = rc(ExprF::App(
-                        e,
-                        v.normalize_to_expr_maybe_alpha(alpha),
-                    ));
+        ensure_sufficient_stack(|| {
+            match self {
+                Value::Lam(x, t, e) => rc(ExprF::Lam(
+                    x.to_label_maybe_alpha(alpha),
+                    t.normalize_to_expr_maybe_alpha(alpha),
+                    e.normalize_to_expr_maybe_alpha(alpha),
+                )),
+                Value::AppliedBuiltin(b, args) => {
+                    let mut e = rc(ExprF::Builtin(*b));
+                    for v in args {
+                        e = rc(ExprF::App(
+                            e,
+                            v.normalize_to_expr_maybe_alpha(alpha),
+                        ));
+                    }
+                    e
                 }
-                e
-            }
-            Value::OptionalSomeClosure(n) => {
-                let a = n.normalize_to_expr_maybe_alpha(alpha);
-                dhall::subexpr!(λ(x: a) -> Some x)
-            }
-            Value::ListConsClosure(a, None) => {
-                // Avoid accidental capture of the new `x` variable
-                let a1 = a.under_binder(Label::from("x"));
-                let a1 = a1.normalize_to_expr_maybe_alpha(alpha);
-                let a = a.normalize_to_expr_maybe_alpha(alpha);
-                dhall::subexpr!(λ(x : a) -> λ(xs : List a1) -> [ x ] # xs)
-            }
-            Value::ListConsClosure(n, Some(v)) => {
-                // Avoid accidental capture of the new `xs` variable
-                let v = v.under_binder(Label::from("xs"));
-                let v = v.normalize_to_expr_maybe_alpha(alpha);
-                let a = n.normalize_to_expr_maybe_alpha(alpha);
-                dhall::subexpr!(λ(xs : List a) -> [ v ] # xs)
-            }
-            Value::NaturalSuccClosure => {
-                dhall::subexpr!(λ(x : Natural) -> x + 1)
-            }
-            Value::Pi(x, t, e) => rc(ExprF::Pi(
-                x.to_label_maybe_alpha(alpha),
-                t.normalize_to_expr_maybe_alpha(alpha),
-                e.normalize_to_expr_maybe_alpha(alpha),
-            )),
-            Value::Var(v) => rc(ExprF::Var(v.to_var(alpha))),
-            Value::Const(c) => rc(ExprF::Const(*c)),
-            Value::BoolLit(b) => rc(ExprF::BoolLit(*b)),
-            Value::NaturalLit(n) => rc(ExprF::NaturalLit(*n)),
-            Value::IntegerLit(n) => rc(ExprF::IntegerLit(*n)),
-            Value::DoubleLit(n) => rc(ExprF::DoubleLit(*n)),
-            Value::EmptyOptionalLit(n) => rc(ExprF::App(
-                rc(ExprF::Builtin(Builtin::OptionalNone)),
-                n.normalize_to_expr_maybe_alpha(alpha),
-            )),
-            Value::NEOptionalLit(n) => {
-                rc(ExprF::SomeLit(n.normalize_to_expr_maybe_alpha(alpha)))
-            }
-            Value::EmptyListLit(n) => {
-                rc(ExprF::EmptyListLit(n.normalize_to_expr_maybe_alpha(alpha)))
-            }
-            Value::NEListLit(elts) => rc(ExprF::NEListLit(
-                elts.iter()
-                    .map(|n| n.normalize_to_expr_maybe_alpha(alpha))
-                    .collect(),
-            )),
-            Value::RecordLit(kvs) => rc(ExprF::RecordLit(
-                kvs.iter()
-                    .map(|(k, v)| {
-                        (k.clone(), v.normalize_to_expr_maybe_alpha(alpha))
-                    })
-                    .collect(),
-            )),
-            Value::RecordType(kts) => rc(ExprF::RecordType(
-                kts.iter()
-                    .map(|(k, v)| {
-                        (k.clone(), v.normalize_to_expr_maybe_alpha(alpha))
-                    })
-                    .collect(),
-            )),
-            Value::UnionType(kts) => rc(ExprF::UnionType(
-                kts.iter()
-                    .map(|(k, v)| {
-                        (
-                            k.clone(),
-                            v.as_ref().map(|v| {
-                                v.normalize_to_expr_maybe_alpha(alpha)
-                            }),
-                        )
-                    })
-                    .collect(),
-            )),
-            Value::UnionConstructor(l, kts) => {
-                let kts = kts
-                    .iter()
-                    .map(|(k, v)| {
-                        (
-                            k.clone(),
-                            v.as_ref().map(|v| {
-                                v.normalize_to_expr_maybe_alpha(alpha)
-                            }),
-                        )
-                    })
-                    .collect();
-                rc(ExprF::Field(rc(ExprF::UnionType(kts)), l.clone()))
-            }
-            Value::UnionLit(l, v, kts) => rc(ExprF::UnionLit(
-                l.clone(),
-                v.normalize_to_expr_maybe_alpha(alpha),
-                kts.iter()
-                    .map(|(k, v)| {
-                        (
-                            k.clone(),
-                            v.as_ref().map(|v| {
-                                v.normalize_to_expr_maybe_alpha(alpha)
-                            }),
-                        )
-                    })
-                    .collect(),
-            )),
-            Value::TextLit(elts) => {
-                use InterpolatedTextContents::{Expr, Text};
-                rc(ExprF::TextLit(
+                Value::OptionalSomeClosure(n) => {
+                    let a = n.normalize_to_expr_maybe_alpha(alpha);
+                    dhall::subexpr!(λ(x: a) -> Some x)
+                }
+                Value::ListConsClosure(a, None) => {
+                    // Avoid accidental capture of the new `x` variable
+                    let a1 = a.under_binder(Label::from("x"));
+                    let a1 = a1.normalize_to_expr_maybe_alpha(alpha);
+                    let a = a.normalize_to_expr_maybe_alpha(alpha);
+                    dhall::subexpr!(λ(x : a) -> λ(xs : List a1) -> [ x ] # xs)
+                }
+                Value::ListConsClosure(n, Some(v)) => {
+                    // Avoid accidental capture of the new `xs` variable
+                    let v = v.under_binder(Label::from("xs"));
+                    let v = v.normalize_to_expr_maybe_alpha(alpha);
+                    let a = n.normalize_to_expr_maybe_alpha(alpha);
+                    dhall::subexpr!(λ(xs : List a) -> [ v ] # xs)
+                }
+                Value::NaturalSuccClosure => {
+                    dhall::subexpr!(λ(x : Natural) -> x + 1)
+                }
+                Value::Pi(x, t, e) => rc(ExprF::Pi(
+                    x.to_label_maybe_alpha(alpha),
+                    t.normalize_to_expr_maybe_alpha(alpha),
+                    e.normalize_to_expr_maybe_alpha(alpha),
+                )),
+                Value::Var(v) => rc(ExprF::Var(v.to_var(alpha))),
+                Value::Const(c) => rc(ExprF::Const(*c)),
+                Value::BoolLit(b) => rc(ExprF::BoolLit(*b)),
+                Value::NaturalLit(n) => rc(ExprF::NaturalLit(n.clone())),
+                Value::IntegerLit(n) => rc(ExprF::IntegerLit(n.clone())),
+                Value::DoubleLit(n) => rc(ExprF::DoubleLit(*n)),
+                Value::EmptyOptionalLit(n) => rc(ExprF::App(
+                    rc(ExprF::Builtin(Builtin::OptionalNone)),
+                    n.normalize_to_expr_maybe_alpha(alpha),
+                )),
+                Value::NEOptionalLit(n) => {
+                    rc(ExprF::SomeLit(n.normalize_to_expr_maybe_alpha(alpha)))
+                }
+                Value::EmptyListLit(n) => rc(ExprF::EmptyListLit(
+                    n.normalize_to_expr_maybe_alpha(alpha),
+                )),
+                Value::NEListLit(elts) => rc(ExprF::NEListLit(
                     elts.iter()
-                        .map(|contents| match contents {
-                            Expr(e) => {
-                                Expr(e.normalize_to_expr_maybe_alpha(alpha))
-                            }
-                            Text(s) => Text(s.clone()),
+                        .map(|n| n.normalize_to_expr_maybe_alpha(alpha))
+                        .collect(),
+                )),
+                Value::RecordLit(kvs) => rc(ExprF::RecordLit(
+                    kvs.iter()
+                        .map(|(k, v)| {
+                            (k.clone(), v.normalize_to_expr_maybe_alpha(alpha))
                         })
                         .collect(),
-                ))
-            }
-            Value::PartialExpr(e) => {
-                rc(e.map_ref_simple(|v| v.normalize_to_expr_maybe_alpha(alpha)))
+                )),
+                Value::RecordType(kts) => rc(ExprF::RecordType(
+                    kts.iter()
+                        .map(|(k, v)| {
+                            (k.clone(), v.normalize_to_expr_maybe_alpha(alpha))
+                        })
+                        .collect(),
+                )),
+                Value::UnionType(kts) => rc(ExprF::UnionType(
+                    kts.iter()
+                        .map(|(k, v)| {
+                            (
+                                k.clone(),
+                                v.as_ref().map(|v| {
+                                    v.normalize_to_expr_maybe_alpha(alpha)
+                                }),
+                            )
+                        })
+                        .collect(),
+                )),
+                Value::UnionConstructor(l, kts) => {
+                    let kts = kts
+                        .iter()
+                        .map(|(k, v)| {
+                            (
+                                k.clone(),
+                                v.as_ref().map(|v| {
+                                    v.normalize_to_expr_maybe_alpha(alpha)
+                                }),
+                            )
+                        })
+                        .collect();
+                    rc(ExprF::Field(rc(ExprF::UnionType(kts)), l.clone()))
+                }
+                Value::UnionLit(l, v, kts) => rc(ExprF::UnionLit(
+                    l.clone(),
+                    v.normalize_to_expr_maybe_alpha(alpha),
+                    kts.iter()
+                        .map(|(k, v)| {
+                            (
+                                k.clone(),
+                                v.as_ref().map(|v| {
+                                    v.normalize_to_expr_maybe_alpha(alpha)
+                                }),
+                            )
+                        })
+                        .collect(),
+                )),
+                Value::TextLit(elts) => {
+                    use InterpolatedTextContents::{Expr, Text};
+                    rc(ExprF::TextLit(
+                        elts.iter()
+                            .map(|contents| match contents {
+                                Expr(e) => {
+                                    Expr(e.normalize_to_expr_maybe_alpha(alpha))
+                                }
+                                Text(s) => Text(s.clone()),
+                            })
+                            .collect(),
+                    ))
+                }
+                Value::PartialExpr(e) => rc(e.map_ref_simple(|v| {
+                    v.normalize_to_expr_maybe_alpha(alpha)
+                })),
             }
-        }
+        })
     }
 
     // Deprecated
@@ -348,8 +351,8 @@ impl Shift for Value {
             Value::Var(v) => Value::Var(v.shift(delta, var)?),
             Value::Const(c) => Value::Const(*c),
             Value::BoolLit(b) => Value::BoolLit(*b),
-            Value::NaturalLit(n) => Value::NaturalLit(*n),
-            Value::IntegerLit(n) => Value::IntegerLit(*n),
+            Value::NaturalLit(n) => Value::NaturalLit(n.clone()),
+            Value::IntegerLit(n) => Value::IntegerLit(n.clone()),
             Value::DoubleLit(n) => Value::DoubleLit(*n),
             Value::EmptyOptionalLit(tth) => {
                 Value::EmptyOptionalLit(tth.shift(delta, var)?)
@@ -491,8 +494,8 @@ impl Subst<Typed> for Value {
             },
             Value::Const(c) => Value::Const(*c),
             Value::BoolLit(b) => Value::BoolLit(*b),
-            Value::NaturalLit(n) => Value::NaturalLit(*n),
-            Value::IntegerLit(n) => Value::IntegerLit(*n),
+            Value::NaturalLit(n) => Value::NaturalLit(n.clone()),
+            Value::IntegerLit(n) => Value::IntegerLit(n.clone()),
             Value::DoubleLit(n) => Value::DoubleLit(*n),
             Value::EmptyOptionalLit(tth) => {
                 Value::EmptyOptionalLit(tth.subst_shift(var, val))
@@ -1,6 +1,6 @@
 use std::io::Error as IOError;
 
-use dhall_syntax::{BinOp, Import, Label, ParseError, V};
+use dhall_syntax::{BinOp, Hash, Import, Label, ParseError, V};
 
 use crate::core::context::TypecheckContext;
 use crate::phase::resolve::ImportStack;
@@ -18,6 +18,8 @@ pub enum Error {
     Resolve(ImportError),
     Typecheck(TypeError),
     Deserialize(String),
+    ResourceLimit(ResourceLimit),
+    Conversion(ConversionError),
 }
 
 #[derive(Debug)]
@@ -25,12 +27,96 @@ pub enum ImportError {
     Recursive(Import, Box<Error>),
     UnexpectedImport(Import),
     ImportCycle(ImportStack, Import),
+    /// Only imports of local files by a relative path can be resolved for
+    /// now.
+    Unsupported(Import),
+    /// The semantic hash of what the import points to could not be computed.
+    Hash(Import, EncodeError),
+    /// What the import points to does not have the hash the import expects.
+    /// Holds the actual hash.
+    HashMismatch(Import, Hash),
+}
+
+/// A value that could not be converted to or from another data format, like
+/// JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    /// Where the offending value is, in the style of `jq`, e.g. `.foo[0]`.
+    /// Empty for the whole value.
+    pub path: String,
+    pub message: String,
+}
+
+impl ConversionError {
+    pub(crate) fn new(message: String) -> Self {
+        ConversionError {
+            path: String::new(),
+            message,
+        }
+    }
+    /// Puts the error under the given field, while propagating it out of
+    /// the record that contains it.
+    pub(crate) fn at_field(mut self, field: &str) -> Self {
+        self.path.insert_str(0, &format!(".{}", field));
+        self
+    }
+    /// Like [ConversionError::at_field], for list elements.
+    pub(crate) fn at_index(mut self, index: usize) -> Self {
+        self.path.insert_str(0, &format!("[{}]", index));
+        self
+    }
+}
+
+/// A limit from [EvalLimits][crate::de::EvalLimits] that normalization
+/// exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    Steps,
+    ListLength,
+    TextLength,
+    Depth,
 }
 
 #[derive(Debug)]
 pub enum DecodeError {
     CBORError(serde_cbor::error::Error),
-    WrongFormatError(String),
+    /// The data is valid CBOR but does not encode an expression. Contains
+    /// what was being decoded.
+    WrongFormatError(CborPath, String),
+    /// The data encodes an expression, but not in the way the standard
+    /// requires. Accepting it would give the same expression several
+    /// encodings, and thus several semantic hashes.
+    NonCanonical(CborPath, String),
+}
+
+/// The position of a value inside a CBOR structure.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CborPath(pub Vec<CborPathSegment>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CborPathSegment {
+    /// An array element
+    Index(usize),
+    /// A map entry
+    Key(String),
+}
+
+impl DecodeError {
+    /// Puts the error under `segment`, while propagating it out of the value
+    /// that contains it.
+    pub(crate) fn at(self, segment: CborPathSegment) -> Self {
+        match self {
+            DecodeError::WrongFormatError(mut path, s) => {
+                path.0.insert(0, segment);
+                DecodeError::WrongFormatError(path, s)
+            }
+            DecodeError::NonCanonical(mut path, s) => {
+                path.0.insert(0, segment);
+                DecodeError::NonCanonical(path, s)
+            }
+            e => e,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -78,6 +164,9 @@ pub(crate) enum TypeMessage {
     MergeHandlerReturnTypeMustNotBeDependent,
     ProjectionMustBeRecord,
     ProjectionMissingEntry,
+    MustCombineRecord(Typed),
+    RecordTypeMergeRequiresRecordType(Typed),
+    FieldCollision(Label),
     Sort,
     RecordTypeDuplicateField,
     UnionTypeDuplicateField,
@@ -111,31 +200,179 @@ impl std::error::Error for TypeMessage {
 }
 
 impl std::fmt::Display for TypeMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use TypeMessage::*;
+        match self {
+            UnboundVariable(v) => write!(f, "Unbound variable `{}`", v),
+            InvalidInputType(t) => {
+                write!(f, "Invalid function input type `{}`", t)
+            }
+            InvalidOutputType(t) => {
+                write!(f, "Invalid function output kind `{}`", t)
+            }
+            NotAFunction(e) => write!(f, "`{}` is not a function", e),
+            TypeMismatch(fun, t, arg) => write!(
+                f,
+                "Wrong type of function argument: `{}` expects a `{}`, \
+                 got `{}`",
+                fun, t, arg
+            ),
+            AnnotMismatch(e, t) => {
+                write!(f, "`{}` does not have the annotated type `{}`", e, t)
+            }
+            Untyped => f.write_str("The expression was not typechecked"),
+            InvalidListElement(i, t, e) => write!(
+                f,
+                "List element {} is `{}`, which is not of type `{}` like \
+                 the first one",
+                i, e, t
+            ),
+            InvalidListType(t) => {
+                write!(f, "Invalid type `{}` for list elements", t)
+            }
+            InvalidOptionalType(t) => {
+                write!(f, "Invalid type `{}` for an optional value", t)
+            }
+            InvalidPredicate(e) => {
+                write!(f, "The condition `{}` is not a `Bool`", e)
+            }
+            IfBranchMismatch(y, z) => write!(
+                f,
+                "The branches `{}` and `{}` of `if` have different types",
+                y, z
+            ),
+            IfBranchMustBeTerm(is_then, e) => write!(
+                f,
+                "The `{}` branch `{}` of `if` is not a term",
+                if *is_then { "then" } else { "else" },
+                e
+            ),
+            InvalidFieldType(x, t) => {
+                write!(f, "Invalid type `{}` for field `{}`", t, x)
+            }
+            NotARecord(x, t) => write!(
+                f,
+                "Cannot access field `{}` of an expression of type `{}`",
+                x, t
+            ),
+            MissingRecordField(x, e) => {
+                write!(f, "`{}` has no field `{}`", e, x)
+            }
+            MissingUnionField(x, t) => {
+                write!(f, "The union type `{}` has no alternative `{}`", t, x)
+            }
+            BinOpTypeMismatch(o, e) => {
+                write!(f, "Wrong type of operand `{}` for `{}`", e, o)
+            }
+            NoDependentTypes(a, b) => {
+                write!(f, "Functions from `{}` to `{}` are not supported", a, b)
+            }
+            InvalidTextInterpolation(e) => {
+                write!(f, "Cannot interpolate `{}`, which is not a `Text`", e)
+            }
+            Merge1ArgMustBeRecord(e) => {
+                write!(f, "The handlers `{}` of `merge` are not a record", e)
+            }
+            Merge2ArgMustBeUnion(e) => {
+                write!(f, "The argument `{}` of `merge` is not a union", e)
+            }
+            MergeEmptyNeedsAnnotation => {
+                f.write_str("An empty `merge` needs a type annotation")
+            }
+            MergeHandlerMissingVariant(x) => {
+                write!(f, "The handler `{}` matches no alternative", x)
+            }
+            MergeVariantMissingHandler(x) => {
+                write!(f, "The alternative `{}` has no handler", x)
+            }
+            MergeAnnotMismatch => f.write_str(
+                "The handlers of `merge` do not return the annotated type",
+            ),
+            MergeHandlerTypeMismatch => f.write_str(
+                "The handlers of `merge` do not all return the same type",
+            ),
+            MergeHandlerReturnTypeMustNotBeDependent => f.write_str(
+                "The return type of a `merge` handler depends on its input",
+            ),
+            ProjectionMustBeRecord => {
+                f.write_str("Only records can be projected")
+            }
+            ProjectionMissingEntry => {
+                f.write_str("Projecting a field that the record does not have")
+            }
+            MustCombineRecord(e) => write!(
+                f,
+                "Only records can be combined, but `{}` is not one",
+                e
+            ),
+            RecordTypeMergeRequiresRecordType(t) => write!(
+                f,
+                "Only record types can be combined, but `{}` is not one",
+                t
+            ),
+            FieldCollision(x) => {
+                write!(f, "The field `{}` would be defined twice", x)
+            }
+            Sort => f.write_str("`Sort` has no type"),
+            RecordTypeDuplicateField => {
+                f.write_str("A field is defined twice in a record type")
+            }
+            UnionTypeDuplicateField => {
+                f.write_str("An alternative is defined twice in a union type")
+            }
+            Unimplemented => {
+                f.write_str("Typechecking this expression is not supported yet")
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for TypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Type error: {}", self.type_message)
+    }
+}
+
+impl std::fmt::Display for ImportError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            // UnboundVariable(_) => {
-            //     f.write_str(include_str!("errors/UnboundVariable.txt"))
-            // }
-            // TypeMismatch(e0, e1, e2) => {
-            //     let template = include_str!("errors/TypeMismatch.txt");
-            //     let s = template
-            //         .replace("$txt0", &format!("{}", e0.as_expr()))
-            //         .replace("$txt1", &format!("{}", e1.as_expr()))
-            //         .replace("$txt2", &format!("{}", e2.as_expr()))
-            //         .replace(
-            //             "$txt3",
-            //             &format!(
-            //                 "{}",
-            //                 e2.get_type()
-            //                     .unwrap()
-            //                     .as_normalized()
-            //                     .unwrap()
-            //                     .as_expr()
-            //             ),
-            //         );
-            //     f.write_str(&s)
-            // }
-            _ => f.write_str("Unhandled error message"),
+            ImportError::Recursive(import, err) => {
+                write!(f, "{}\n  while importing {}", err, import)
+            }
+            ImportError::UnexpectedImport(import) => {
+                write!(f, "Imports are not allowed here: {}", import)
+            }
+            ImportError::ImportCycle(stack, import) => {
+                f.write_str("Import cycle: ")?;
+                for i in stack {
+                    write!(f, "{} → ", i)?;
+                }
+                write!(f, "{}", import)
+            }
+            ImportError::Unsupported(import) => write!(
+                f,
+                "Cannot import {}: only relative paths to local files are \
+                 supported",
+                import
+            ),
+            ImportError::Hash(import, err) => {
+                write!(f, "Cannot compute the hash of {}: {}", import, err)
+            }
+            ImportError::HashMismatch(import, actual) => write!(
+                f,
+                "Hash mismatch for {}: the imported expression has hash {}",
+                import, actual
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            EncodeError::CBORError(err) => {
+                write!(f, "Cannot encode to CBOR: {}", err)
+            }
         }
     }
 }
@@ -145,12 +382,63 @@ impl std::fmt::Display for Error {
         match self {
             Error::IO(err) => write!(f, "{}", err),
             Error::Parse(err) => write!(f, "{}", err),
-            Error::Decode(err) => write!(f, "{:?}", err),
-            Error::Encode(err) => write!(f, "{:?}", err),
-            Error::Resolve(err) => write!(f, "{:?}", err),
-            Error::Typecheck(err) => write!(f, "{:?}", err),
+            Error::Decode(err) => write!(f, "{}", err),
+            Error::Encode(err) => write!(f, "{}", err),
+            Error::Resolve(err) => write!(f, "{}", err),
+            Error::Typecheck(err) => write!(f, "{}", err),
             Error::Deserialize(err) => write!(f, "{}", err),
+            Error::ResourceLimit(limit) => write!(f, "{}", limit),
+            Error::Conversion(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DecodeError::CBORError(err) => write!(f, "Invalid CBOR: {}", err),
+            DecodeError::WrongFormatError(path, what) => {
+                write!(f, "Invalid {} at {}", what, path)
+            }
+            DecodeError::NonCanonical(path, what) => {
+                write!(f, "Non-canonical encoding at {}: {}", path, what)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "At `{}`: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::fmt::Display for CborPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("$")?;
+        for segment in &self.0 {
+            match segment {
+                CborPathSegment::Index(i) => write!(f, "[{}]", i)?,
+                CborPathSegment::Key(k) => write!(f, "[{:?}]", k)?,
+            }
         }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use ResourceLimit::*;
+        f.write_str(match self {
+            Steps => "Normalization exceeded the maximum number of steps",
+            ListLength => "Normalization built a list that is too long",
+            TextLength => "Normalization built a text that is too long",
+            Depth => "Normalization exceeded the maximum recursion depth",
+        })
     }
 }
 
@@ -175,6 +463,11 @@ impl From<EncodeError> for Error {
         Error::Encode(err)
     }
 }
+impl From<ConversionError> for Error {
+    fn from(err: ConversionError) -> Error {
+        Error::Conversion(err)
+    }
+}
 impl From<ImportError> for Error {
     fn from(err: ImportError) -> Error {
         Error::Resolve(err)
@@ -127,8 +127,12 @@
 mod tests;
 
 pub(crate) mod api;
+#[cfg(any(test, feature = "arbitrary"))]
+pub mod arbitrary;
 pub(crate) mod core;
 pub mod error;
 pub(crate) mod phase;
+pub mod pipeline;
+pub mod profile;
 
 pub use api::*;
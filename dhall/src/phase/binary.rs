@@ -1,16 +1,17 @@
-use itertools::Itertools;
-use serde_cbor::value::value as cbor;
+use num_bigint::{BigInt, Sign};
+use num_traits::{One, ToPrimitive};
+use serde_cbor::value as cbor;
+use std::convert::TryFrom;
 use std::iter::FromIterator;
 
 use dhall_syntax::map::DupTreeMap;
 use dhall_syntax::{
     rc, ExprF, FilePrefix, Hash, Import, ImportHashed, ImportLocation,
-    ImportMode, Integer, InterpolatedText, Label, Natural, Scheme, SubExpr,
-    URL, V,
+    ImportMode, InterpolatedText, Label, Natural, Scheme, SubExpr, URL, V, X,
 };
 
-use crate::error::{DecodeError, EncodeError};
-use crate::phase::{DecodedSubExpr, ParsedSubExpr};
+use crate::error::{CborPath, CborPathSegment, DecodeError, EncodeError};
+use crate::phase::DecodedSubExpr;
 
 pub fn decode(data: &[u8]) -> Result<DecodedSubExpr, DecodeError> {
     match serde_cbor::de::from_slice(data) {
@@ -19,367 +20,431 @@ pub fn decode(data: &[u8]) -> Result<DecodedSubExpr, DecodeError> {
     }
 }
 
-//TODO: encode normalized expression too
-pub fn encode(expr: &ParsedSubExpr) -> Result<Vec<u8>, EncodeError> {
+pub fn encode<N, E: EncodeEmbed>(
+    expr: &SubExpr<N, E>,
+) -> Result<Vec<u8>, EncodeError> {
     serde_cbor::ser::to_vec(&Serialize::Expr(expr))
         .map_err(|e| EncodeError::CBORError(e))
 }
 
+// The CBOR library does not expose the width in which integers and floats
+// were encoded, so non-minimal widths are not detected. Everything else that
+// the standard requires of an encoding is checked, since an expression with
+// several encodings would get several semantic hashes.
 fn cbor_value_to_dhall(
     data: &cbor::Value,
 ) -> Result<DecodedSubExpr, DecodeError> {
     use cbor::Value::*;
-    use dhall_syntax::{BinOp, Builtin, Const};
+    use dhall_syntax::{Builtin, Const};
     use ExprF::*;
     Ok(rc(match data {
-        String(s) => match Builtin::parse(s) {
+        Text(s) => match Builtin::parse(s) {
             Some(b) => ExprF::Builtin(b),
             None => match s.as_str() {
-                "True" => BoolLit(true),
-                "False" => BoolLit(false),
                 "Type" => Const(Const::Type),
                 "Kind" => Const(Const::Kind),
                 "Sort" => Const(Const::Sort),
-                _ => Err(DecodeError::WrongFormatError("builtin".to_owned()))?,
+                "True" | "False" => {
+                    non_canonical("booleans are encoded as CBOR booleans")?
+                }
+                _ => wrong_format("builtin")?,
             },
         },
-        U64(n) => Var(V(Label::from("_"), *n as usize)),
-        F64(x) => DoubleLit((*x).into()),
+        Integer(n) => Var(V(Label::from("_"), var_index(*n)?)),
+        Float(x) => DoubleLit((*x).into()),
         Bool(b) => BoolLit(*b),
-        Array(vec) => match vec.as_slice() {
-            [String(l), U64(n)] => {
-                let l = Label::from(l.as_str());
-                Var(V(l, *n as usize))
-            }
-            [U64(0), f, args..] => {
-                let mut f = cbor_value_to_dhall(&f)?;
-                for a in args {
-                    let a = cbor_value_to_dhall(&a)?;
-                    f = rc(App(f, a))
-                }
-                return Ok(f);
-            }
-            [U64(1), x, y] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                Lam(Label::from("_"), x, y)
-            }
-            [U64(1), String(l), x, y] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                let l = Label::from(l.as_str());
-                Lam(l, x, y)
-            }
-            [U64(2), x, y] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                Pi(Label::from("_"), x, y)
-            }
-            [U64(2), String(l), x, y] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                let l = Label::from(l.as_str());
-                Pi(l, x, y)
-            }
-            [U64(3), U64(n), x, y] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                use BinOp::*;
-                let op = match n {
-                    0 => BoolOr,
-                    1 => BoolAnd,
-                    2 => BoolEQ,
-                    3 => BoolNE,
-                    4 => NaturalPlus,
-                    5 => NaturalTimes,
-                    6 => TextAppend,
-                    7 => ListAppend,
-                    8 => RecursiveRecordMerge,
-                    9 => RightBiasedRecordMerge,
-                    10 => RecursiveRecordTypeMerge,
-                    11 => ImportAlt,
-                    _ => {
-                        Err(DecodeError::WrongFormatError("binop".to_owned()))?
-                    }
-                };
-                BinOp(op, x, y)
-            }
-            [U64(4), t] => {
-                let t = cbor_value_to_dhall(&t)?;
-                EmptyListLit(t)
-            }
-            [U64(4), Null, rest..] => {
-                let rest = rest
-                    .iter()
-                    .map(cbor_value_to_dhall)
-                    .collect::<Result<Vec<_>, _>>()?;
-                NEListLit(rest)
-            }
-            [U64(5), t] => {
-                let t = cbor_value_to_dhall(&t)?;
-                OldOptionalLit(None, t)
-            }
-            [U64(5), Null, x] => {
-                let x = cbor_value_to_dhall(&x)?;
-                SomeLit(x)
-            }
-            [U64(5), t, x] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let t = cbor_value_to_dhall(&t)?;
-                OldOptionalLit(Some(x), t)
-            }
-            [U64(6), x, y] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                Merge(x, y, None)
-            }
-            [U64(6), x, y, z] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                let z = cbor_value_to_dhall(&z)?;
-                Merge(x, y, Some(z))
-            }
-            [U64(7), Object(map)] => {
-                let map = cbor_map_to_dhall_map(map)?;
-                RecordType(map)
-            }
-            [U64(8), Object(map)] => {
-                let map = cbor_map_to_dhall_map(map)?;
-                RecordLit(map)
-            }
-            [U64(9), x, String(l)] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let l = Label::from(l.as_str());
-                Field(x, l)
-            }
-            [U64(11), Object(map)] => {
-                let map = cbor_map_to_dhall_opt_map(map)?;
-                UnionType(map)
-            }
-            [U64(12), String(l), x, Object(map)] => {
-                let map = cbor_map_to_dhall_opt_map(map)?;
-                let x = cbor_value_to_dhall(&x)?;
-                let l = Label::from(l.as_str());
-                UnionLit(l, x, map)
+        Array(vec) => return cbor_array_to_dhall(vec),
+        _ => wrong_format("expression")?,
+    }))
+}
+
+fn cbor_array_to_dhall(
+    vec: &[cbor::Value],
+) -> Result<DecodedSubExpr, DecodeError> {
+    use cbor::Value::*;
+    use dhall_syntax::BinOp;
+    use ExprF::*;
+    let expr = |i: usize| at(i, cbor_value_to_dhall(&vec[i]));
+    let label = |i: usize| at(i, cbor_value_to_label(&vec[i]));
+    // A label that is not `_`, which would have been left out
+    let named_label = |i: usize| {
+        let l = label(i)?;
+        if l == "_".into() {
+            at(i, non_canonical("the label `_` is left out"))?
+        }
+        Ok(l)
+    };
+    Ok(rc(match vec {
+        [Text(_), Integer(n)] => Var(V(named_label(0)?, at(1, var_index(*n))?)),
+        [Integer(0), _, args..] if !args.is_empty() => {
+            if is_tagged(&vec[1], 0) {
+                at(1, non_canonical("nested applications are flattened"))?
             }
-            [U64(14), x, y, z] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                let z = cbor_value_to_dhall(&z)?;
-                BoolIf(x, y, z)
+            let mut f = expr(1)?;
+            for i in 2..vec.len() {
+                f = rc(App(f, expr(i)?))
             }
-            [U64(15), U64(x)] => NaturalLit(*x as Natural),
-            [U64(16), U64(x)] => IntegerLit(*x as Integer),
-            [U64(16), I64(x)] => IntegerLit(*x as Integer),
-            [U64(18), String(first), rest..] => {
-                TextLit(InterpolatedText::from((
-                    first.clone(),
-                    rest.iter()
-                        .tuples()
-                        .map(|(x, y)| {
-                            let x = cbor_value_to_dhall(&x)?;
-                            let y = match y {
-                                String(s) => s.clone(),
-                                _ => Err(DecodeError::WrongFormatError(
-                                    "text".to_owned(),
-                                ))?,
-                            };
-                            Ok((x, y))
-                        })
-                        .collect::<Result<_, _>>()?,
-                )))
+            return Ok(f);
+        }
+        [Integer(1), _, _] => Lam(Label::from("_"), expr(1)?, expr(2)?),
+        [Integer(1), Text(_), _, _] => Lam(named_label(1)?, expr(2)?, expr(3)?),
+        [Integer(2), _, _] => Pi(Label::from("_"), expr(1)?, expr(2)?),
+        [Integer(2), Text(_), _, _] => Pi(named_label(1)?, expr(2)?, expr(3)?),
+        [Integer(3), Integer(n), _, _] => {
+            use BinOp::*;
+            let op = match n {
+                0 => BoolOr,
+                1 => BoolAnd,
+                2 => BoolEQ,
+                3 => BoolNE,
+                4 => NaturalPlus,
+                5 => NaturalTimes,
+                6 => TextAppend,
+                7 => ListAppend,
+                8 => RecursiveRecordMerge,
+                9 => RightBiasedRecordMerge,
+                10 => RecursiveRecordTypeMerge,
+                11 => ImportAlt,
+                _ => at(1, wrong_format("operator"))?,
+            };
+            BinOp(op, expr(2)?, expr(3)?)
+        }
+        [Integer(4), _] => EmptyListLit(expr(1)?),
+        [Integer(4), Null, rest..] if !rest.is_empty() => {
+            NEListLit((2..vec.len()).map(expr).collect::<Result<Vec<_>, _>>()?)
+        }
+        [Integer(5), _] => OldOptionalLit(None, expr(1)?),
+        [Integer(5), Null, _] => SomeLit(expr(2)?),
+        [Integer(5), _, _] => OldOptionalLit(Some(expr(2)?), expr(1)?),
+        [Integer(6), _, _] => Merge(expr(1)?, expr(2)?, None),
+        [Integer(6), _, _, _] => Merge(expr(1)?, expr(2)?, Some(expr(3)?)),
+        [Integer(7), Map(map)] => {
+            RecordType(at(1, cbor_map_to_dhall_map(map))?)
+        }
+        [Integer(8), Map(map)] => RecordLit(at(1, cbor_map_to_dhall_map(map))?),
+        [Integer(9), _, Text(_)] => Field(expr(1)?, label(2)?),
+        [Integer(10), _, ..] => Projection(
+            expr(1)?,
+            (2..vec.len()).map(label).collect::<Result<_, _>>()?,
+        ),
+        [Integer(11), Map(map)] => {
+            UnionType(at(1, cbor_map_to_dhall_opt_map(map))?)
+        }
+        [Integer(12), Text(_), _, Map(map)] => UnionLit(
+            label(1)?,
+            expr(2)?,
+            at(3, cbor_map_to_dhall_opt_map(map))?,
+        ),
+        [Integer(14), _, _, _] => BoolIf(expr(1)?, expr(2)?, expr(3)?),
+        [Integer(15), x] => {
+            let n = at(1, cbor_value_to_integer(x))?;
+            match n.to_biguint() {
+                Some(n) => NaturalLit(Natural::from(n)),
+                None => at(1, wrong_format("natural"))?,
             }
-            [U64(24), hash, U64(mode), U64(scheme), rest..] => {
-                let mode = match mode {
-                    1 => ImportMode::RawText,
-                    _ => ImportMode::Code,
-                };
-                let hash = match hash {
-                    Null => None,
-                    Array(vec) => match vec.as_slice() {
-                        [String(protocol), String(hash)] => Some(Hash {
-                            protocol: protocol.clone(),
-                            hash: hash.clone(),
-                        }),
-                        _ => Err(DecodeError::WrongFormatError(
-                            "import/hash".to_owned(),
-                        ))?,
-                    },
-                    _ => Err(DecodeError::WrongFormatError(
-                        "import/hash".to_owned(),
-                    ))?,
-                };
-                let mut rest = rest.iter();
-                let location = match scheme {
-                    0 | 1 => {
-                        let scheme = match scheme {
-                            0 => Scheme::HTTP,
-                            _ => Scheme::HTTPS,
-                        };
-                        let headers = match rest.next() {
-                            Some(Null) => None,
-                            Some(x) => {
-                                match cbor_value_to_dhall(&x)?.as_ref() {
-                                    Embed(import) => Some(Box::new(
-                                        import.location_hashed.clone(),
-                                    )),
-                                    _ => Err(DecodeError::WrongFormatError(
-                                        "import/remote/headers".to_owned(),
-                                    ))?,
-                                }
-                            }
-                            _ => Err(DecodeError::WrongFormatError(
-                                "import/remote/headers".to_owned(),
-                            ))?,
-                        };
-                        let authority = match rest.next() {
-                            Some(String(s)) => s.to_owned(),
-                            _ => Err(DecodeError::WrongFormatError(
-                                "import/remote/authority".to_owned(),
-                            ))?,
-                        };
-                        let query = match rest.next_back() {
-                            Some(Null) => None,
-                            Some(String(s)) => Some(s.to_owned()),
-                            _ => Err(DecodeError::WrongFormatError(
-                                "import/remote/query".to_owned(),
-                            ))?,
-                        };
-                        let path = rest
-                            .map(|s| match s.as_string() {
-                                Some(s) => Ok(s.clone()),
-                                None => Err(DecodeError::WrongFormatError(
-                                    "import/remote/path".to_owned(),
-                                )),
-                            })
-                            .collect::<Result<_, _>>()?;
-                        ImportLocation::Remote(URL {
-                            scheme,
-                            authority,
-                            path,
-                            query,
-                            headers,
-                        })
-                    }
-                    2 | 3 | 4 | 5 => {
-                        let prefix = match scheme {
-                            2 => FilePrefix::Absolute,
-                            3 => FilePrefix::Here,
-                            4 => FilePrefix::Parent,
-                            5 => FilePrefix::Home,
-                            _ => Err(DecodeError::WrongFormatError(
-                                "import/local/prefix".to_owned(),
-                            ))?,
-                        };
-                        let path = rest
-                            .map(|s| match s.as_string() {
-                                Some(s) => Ok(s.clone()),
-                                None => Err(DecodeError::WrongFormatError(
-                                    "import/local/path".to_owned(),
-                                )),
-                            })
-                            .collect::<Result<_, _>>()?;
-                        ImportLocation::Local(prefix, path)
-                    }
-                    6 => {
-                        let env = match rest.next() {
-                            Some(String(s)) => s.to_owned(),
-                            _ => Err(DecodeError::WrongFormatError(
-                                "import/env".to_owned(),
-                            ))?,
-                        };
-                        ImportLocation::Env(env)
-                    }
-                    7 => ImportLocation::Missing,
-                    _ => Err(DecodeError::WrongFormatError(
-                        "import/type".to_owned(),
-                    ))?,
-                };
-                Embed(Import {
-                    mode,
-                    location_hashed: ImportHashed { hash, location },
+        }
+        [Integer(16), x] => {
+            let n = at(1, cbor_value_to_integer(x))?;
+            IntegerLit(n.into())
+        }
+        [Integer(18), Text(first), rest..] if rest.len() % 2 == 0 => {
+            let chunks = (2..vec.len())
+                .step_by(2)
+                .map(|i| {
+                    let x = expr(i)?;
+                    let y = match &vec[i + 1] {
+                        Text(s) => s.clone(),
+                        _ => at(i + 1, wrong_format("text"))?,
+                    };
+                    Ok((x, y))
                 })
+                .collect::<Result<_, _>>()?;
+            TextLit(InterpolatedText::from((first.clone(), chunks)))
+        }
+        [Integer(24), ..] => Embed(cbor_array_to_import(vec)?),
+        [Integer(25), rest..] if rest.len() >= 4 && rest.len() % 3 == 1 => {
+            let body = vec.len() - 1;
+            if is_tagged(&vec[body], 25) {
+                at(body, non_canonical("nested lets are flattened"))?
             }
-            [U64(25), bindings..] => {
-                let mut tuples = bindings.iter().tuples();
-                let bindings = (&mut tuples)
-                    .map(|(x, t, v)| {
-                        let x = x.as_string().ok_or_else(|| {
-                            DecodeError::WrongFormatError(
-                                "let/label".to_owned(),
-                            )
-                        })?;
-                        let x = Label::from(x.as_str());
-                        let t = match t {
-                            Null => None,
-                            t => Some(cbor_value_to_dhall(&t)?),
-                        };
-                        let v = cbor_value_to_dhall(&v)?;
-                        Ok((x, t, v))
-                    })
-                    .collect::<Result<Vec<_>, _>>()?;
-                let expr = tuples.into_buffer().next().ok_or_else(|| {
-                    DecodeError::WrongFormatError("let/expr".to_owned())
-                })?;
-                let expr = cbor_value_to_dhall(expr)?;
-                return Ok(bindings
-                    .into_iter()
-                    .rev()
-                    .fold(expr, |acc, (x, t, v)| rc(Let(x, t, v, acc))));
-            }
-            [U64(26), x, y] => {
-                let x = cbor_value_to_dhall(&x)?;
-                let y = cbor_value_to_dhall(&y)?;
-                Annot(x, y)
+            let bindings = (1..body)
+                .step_by(3)
+                .map(|i| {
+                    let t = match &vec[i + 1] {
+                        Null => None,
+                        _ => Some(expr(i + 1)?),
+                    };
+                    Ok((label(i)?, t, expr(i + 2)?))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(bindings
+                .into_iter()
+                .rev()
+                .fold(expr(body)?, |acc, (x, t, v)| rc(Let(x, t, v, acc))));
+        }
+        [Integer(26), _, _] => Annot(expr(1)?, expr(2)?),
+        _ => wrong_format("expression")?,
+    }))
+}
+
+fn cbor_array_to_import(vec: &[cbor::Value]) -> Result<Import, DecodeError> {
+    use cbor::Value::*;
+    let mode = match &vec[..] {
+        [_, _, Integer(0), Integer(_), ..] => ImportMode::Code,
+        [_, _, Integer(1), Integer(_), ..] => ImportMode::RawText,
+        [_, _, _, Integer(_), ..] => at(2, wrong_format("import mode"))?,
+        _ => wrong_format("import")?,
+    };
+    let hash = match &vec[1] {
+        Null => None,
+        Array(h) => match h.as_slice() {
+            [Text(protocol), Text(hash)]
+                if protocol == "sha256"
+                    && hash.len() == 64
+                    && hash.chars().all(|c| c.is_digit(16)) =>
+            {
+                Some(Hash {
+                    protocol: protocol.clone(),
+                    hash: hash.clone(),
+                })
             }
-            _ => Err(DecodeError::WrongFormatError(format!("{:?}", data)))?,
+            _ => at(1, wrong_format("import hash"))?,
         },
-        _ => Err(DecodeError::WrongFormatError(format!("{:?}", data)))?,
-    }))
+        _ => at(1, wrong_format("import hash"))?,
+    };
+    // The components of the location start at index 4
+    let rest_len = vec.len() - 4;
+    let text = |i: usize, what: &str| match &vec[i] {
+        Text(s) => Ok(s.clone()),
+        _ => at(i, wrong_format(what)),
+    };
+    let location = match &vec[3] {
+        Integer(scheme @ 0) | Integer(scheme @ 1) if rest_len >= 3 => {
+            let scheme = match scheme {
+                0 => Scheme::HTTP,
+                _ => Scheme::HTTPS,
+            };
+            let headers = match &vec[4] {
+                Null => None,
+                x => match at(4, cbor_value_to_dhall(x))?.as_ref() {
+                    ExprF::Embed(import) => {
+                        Some(Box::new(import.location_hashed.clone()))
+                    }
+                    _ => at(4, wrong_format("import headers"))?,
+                },
+            };
+            let authority = text(5, "URL authority")?;
+            let query_index = vec.len() - 1;
+            let query = match &vec[query_index] {
+                Null => None,
+                _ => Some(text(query_index, "URL query")?),
+            };
+            let path = (6..query_index)
+                .map(|i| text(i, "URL path component"))
+                .collect::<Result<_, _>>()?;
+            ImportLocation::Remote(URL {
+                scheme,
+                authority,
+                path,
+                query,
+                headers,
+            })
+        }
+        Integer(scheme @ 2..=5) if rest_len > 0 => {
+            let prefix = match scheme {
+                2 => FilePrefix::Absolute,
+                3 => FilePrefix::Here,
+                4 => FilePrefix::Parent,
+                _ => FilePrefix::Home,
+            };
+            let path = (4..vec.len())
+                .map(|i| text(i, "path component"))
+                .collect::<Result<_, _>>()?;
+            ImportLocation::Local(prefix, path)
+        }
+        Integer(6) if rest_len == 1 => {
+            ImportLocation::Env(text(4, "environment variable")?)
+        }
+        Integer(7) if rest_len == 0 => ImportLocation::Missing,
+        _ => at(3, wrong_format("import type"))?,
+    };
+    Ok(Import {
+        mode,
+        location_hashed: ImportHashed { hash, location },
+    })
 }
 
 fn cbor_map_to_dhall_map<'a, T>(
-    map: impl IntoIterator<Item = (&'a cbor::ObjectKey, &'a cbor::Value)>,
+    map: impl IntoIterator<Item = (&'a cbor::Value, &'a cbor::Value)>,
 ) -> Result<T, DecodeError>
 where
     T: FromIterator<(Label, DecodedSubExpr)>,
 {
     map.into_iter()
         .map(|(k, v)| -> Result<(_, _), _> {
-            let k = k.as_string().ok_or_else(|| {
-                DecodeError::WrongFormatError("map/key".to_owned())
-            })?;
-            let v = cbor_value_to_dhall(v)?;
-            Ok((Label::from(k.as_ref()), v))
+            let at_key = |e: DecodeError| e.at(map_key(k));
+            let k = cbor_value_to_label(k).map_err(at_key)?;
+            let v = cbor_value_to_dhall(v).map_err(at_key)?;
+            Ok((k, v))
         })
         .collect::<Result<_, _>>()
 }
 
 fn cbor_map_to_dhall_opt_map<'a, T>(
-    map: impl IntoIterator<Item = (&'a cbor::ObjectKey, &'a cbor::Value)>,
+    map: impl IntoIterator<Item = (&'a cbor::Value, &'a cbor::Value)>,
 ) -> Result<T, DecodeError>
 where
     T: FromIterator<(Label, Option<DecodedSubExpr>)>,
 {
     map.into_iter()
         .map(|(k, v)| -> Result<(_, _), _> {
-            let k = k.as_string().ok_or_else(|| {
-                DecodeError::WrongFormatError("map/key".to_owned())
-            })?;
+            let at_key = |e: DecodeError| e.at(map_key(k));
+            let k = cbor_value_to_label(k).map_err(at_key)?;
             let v = match v {
                 cbor::Value::Null => None,
-                _ => Some(cbor_value_to_dhall(v)?),
+                _ => Some(cbor_value_to_dhall(v).map_err(at_key)?),
             };
-            Ok((Label::from(k.as_ref()), v))
+            Ok((k, v))
         })
         .collect::<Result<_, _>>()
 }
 
-enum Serialize<'a> {
-    Expr(&'a ParsedSubExpr),
+fn wrong_format<T>(what: &str) -> Result<T, DecodeError> {
+    Err(DecodeError::WrongFormatError(
+        CborPath::default(),
+        what.to_owned(),
+    ))
+}
+
+fn non_canonical<T>(reason: &str) -> Result<T, DecodeError> {
+    Err(DecodeError::NonCanonical(
+        CborPath::default(),
+        reason.to_owned(),
+    ))
+}
+
+/// Locates an error in the `i`th element of the current array.
+fn at<T>(i: usize, r: Result<T, DecodeError>) -> Result<T, DecodeError> {
+    r.map_err(|e| e.at(CborPathSegment::Index(i)))
+}
+
+fn map_key(k: &cbor::Value) -> CborPathSegment {
+    match k {
+        cbor::Value::Text(s) => CborPathSegment::Key(s.clone()),
+        _ => CborPathSegment::Key(format!("{:?}", k)),
+    }
+}
+
+/// Whether `v` is the encoding of an expression with the given tag.
+fn is_tagged(v: &cbor::Value, tag: i128) -> bool {
+    match v {
+        cbor::Value::Array(vec) => {
+            vec.first() == Some(&cbor::Value::Integer(tag))
+        }
+        _ => false,
+    }
+}
+
+fn var_index(n: i128) -> Result<usize, DecodeError> {
+    usize::try_from(n).or_else(|_| wrong_format("variable index"))
+}
+
+/// Reads a label, which must be something that can be written in the source,
+/// possibly quoted.
+fn cbor_value_to_label(v: &cbor::Value) -> Result<Label, DecodeError> {
+    let is_label_char = |c: char| match c {
+        '\u{20}'..='\u{5F}' | '\u{61}'..='\u{7E}' => true,
+        _ => false,
+    };
+    match v {
+        cbor::Value::Text(s)
+            if !s.is_empty() && s.chars().all(is_label_char) =>
+        {
+            Ok(Label::from(s.as_str()))
+        }
+        _ => wrong_format("label"),
+    }
+}
+
+/// Reads a plain CBOR integer or a bignum (tags 2 and 3). Bignums are only
+/// allowed for numbers that do not fit in a plain integer.
+fn cbor_value_to_integer(v: &cbor::Value) -> Result<BigInt, DecodeError> {
+    match v {
+        cbor::Value::Integer(n) => Ok(BigInt::from(*n)),
+        cbor::Value::Tag(tag @ 2, x) | cbor::Value::Tag(tag @ 3, x) => {
+            let bytes = match &**x {
+                cbor::Value::Bytes(b) => b,
+                _ => return wrong_format("bignum"),
+            };
+            // CBOR integers cover the range [-2^64, 2^64 - 1]
+            if bytes.len() <= 8 {
+                return non_canonical("bignum for a small integer");
+            }
+            if bytes[0] == 0 {
+                return non_canonical("bignum with leading zeros");
+            }
+            let magnitude = BigInt::from_bytes_be(Sign::Plus, bytes);
+            Ok(match tag {
+                2 => magnitude,
+                _ => -magnitude - BigInt::one(),
+            })
+        }
+        _ => wrong_format("integer"),
+    }
+}
+
+/// Encodes an integer as a plain CBOR integer when it fits, and as a bignum
+/// (tags 2 and 3) otherwise.
+fn integer_to_cbor(n: &dhall_syntax::Integer) -> cbor::Value {
+    if let Some(n) = n.to_i64() {
+        return cbor::Value::Integer(n.into());
+    }
+    let n = n.to_bigint();
+    // CBOR integers cover the range [-2^64, 2^64 - 1]
+    let (tag, magnitude) = match n.sign() {
+        Sign::Minus => (3, -&n - BigInt::one()),
+        _ => (2, n.clone()),
+    };
+    match magnitude.to_u64() {
+        Some(_) => cbor::Value::Integer(n.to_i128().unwrap()),
+        None => {
+            let (_, bytes) = magnitude.to_bytes_be();
+            cbor::Value::Tag(tag, Box::new(cbor::Value::Bytes(bytes)))
+        }
+    }
+}
+
+/// What can be embedded in an expression that gets encoded: imports for
+/// parsed expressions, and nothing for resolved ones.
+pub trait EncodeEmbed {
+    fn serialize_embed<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer;
+}
+
+impl EncodeEmbed for Import {
+    fn serialize_embed<S>(&self, ser: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        serialize_import(ser, self)
+    }
+}
+
+impl EncodeEmbed for X {
+    fn serialize_embed<S>(&self, _: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::ser::Serializer,
+    {
+        match *self {}
+    }
+}
+
+enum Serialize<'a, N, E> {
+    Expr(&'a SubExpr<N, E>),
     CBOR(cbor::Value),
-    RecordMap(&'a DupTreeMap<Label, ParsedSubExpr>),
-    UnionMap(&'a DupTreeMap<Label, Option<ParsedSubExpr>>),
+    RecordMap(&'a DupTreeMap<Label, SubExpr<N, E>>),
+    UnionMap(&'a DupTreeMap<Label, Option<SubExpr<N, E>>>),
     Import(&'a Import),
 }
 
@@ -400,44 +465,49 @@ macro_rules! ser_seq {
     }};
 }
 
-fn serialize_subexpr<S>(ser: S, e: &ParsedSubExpr) -> Result<S::Ok, S::Error>
+fn serialize_subexpr<'a, S, N, E>(
+    ser: S,
+    e: &'a SubExpr<N, E>,
+) -> Result<S::Ok, S::Error>
 where
     S: serde::ser::Serializer,
+    E: EncodeEmbed,
 {
-    use cbor::Value::{String, I64, U64};
+    use cbor::Value::Integer;
     use dhall_syntax::ExprF::*;
     use std::iter::once;
 
     use self::Serialize::{RecordMap, UnionMap};
-    fn expr(x: &ParsedSubExpr) -> self::Serialize<'_> {
+    // Closures rather than functions, so that they share `N` and `E`
+    let expr = |x: &'a SubExpr<N, E>| -> self::Serialize<'a, N, E> {
         self::Serialize::Expr(x)
-    }
-    fn cbor<'a>(v: cbor::Value) -> self::Serialize<'a> {
+    };
+    let cbor = |v: cbor::Value| -> self::Serialize<'a, N, E> {
         self::Serialize::CBOR(v)
-    }
-    fn tag<'a>(x: u64) -> self::Serialize<'a> {
-        cbor(U64(x))
-    }
-    fn null<'a>() -> self::Serialize<'a> {
-        cbor(cbor::Value::Null)
-    }
-    fn label<'a>(l: &Label) -> self::Serialize<'a> {
-        cbor(cbor::Value::String(l.into()))
-    }
+    };
+    let tag = |x: u64| cbor(Integer(x.into()));
+    let null = || cbor(cbor::Value::Null);
+    let label = |l: &Label| cbor(cbor::Value::Text(l.into()));
 
     match e.as_ref() {
         Const(c) => ser.serialize_str(&c.to_string()),
         Builtin(b) => ser.serialize_str(&b.to_string()),
         BoolLit(b) => ser.serialize_bool(*b),
-        NaturalLit(n) => ser_seq!(ser; tag(15), U64(*n as u64)),
-        IntegerLit(n) => ser_seq!(ser; tag(16), I64(*n as i64)),
+        NaturalLit(n) => {
+            let n = match n.to_u64() {
+                Some(n) => Integer(n.into()),
+                None => integer_to_cbor(&n.clone().into()),
+            };
+            ser_seq!(ser; tag(15), cbor(n))
+        }
+        IntegerLit(n) => ser_seq!(ser; tag(16), cbor(integer_to_cbor(n))),
         DoubleLit(n) => {
             let n: f64 = (*n).into();
             ser.serialize_f64(n)
         }
         BoolIf(x, y, z) => ser_seq!(ser; tag(14), expr(x), expr(y), expr(z)),
         Var(V(l, n)) if l == &"_".into() => ser.serialize_u64(*n as u64),
-        Var(V(l, n)) => ser_seq!(ser; label(l), U64(*n as u64)),
+        Var(V(l, n)) => ser_seq!(ser; label(l), Integer(*n as i128)),
         Lam(l, x, y) if l == &"_".into() => {
             ser_seq!(ser; tag(1), expr(x), expr(y))
         }
@@ -446,12 +516,21 @@ where
             ser_seq!(ser; tag(2), expr(x), expr(y))
         }
         Pi(l, x, y) => ser_seq!(ser; tag(2), label(l), expr(x), expr(y)),
-        // TODO: multilet
-        Let(l, None, x, y) => {
-            ser_seq!(ser; tag(25), label(l), null(), expr(x), expr(y))
-        }
-        Let(l, Some(t), x, y) => {
-            ser_seq!(ser; tag(25), label(l), expr(t), expr(x), expr(y))
+        Let(_, _, _, _) => {
+            // Nested lets are flattened into a single one
+            let mut items = vec![tag(25)];
+            let mut e = e;
+            while let Let(l, t, x, y) = e.as_ref() {
+                items.push(label(l));
+                items.push(match t {
+                    Some(t) => expr(t),
+                    None => null(),
+                });
+                items.push(expr(x));
+                e = y;
+            }
+            items.push(expr(e));
+            ser.collect_seq(items)
         }
         App(_, _) => {
             let (f, args) = collect_nested_applications(e);
@@ -473,7 +552,7 @@ where
             use dhall_syntax::InterpolatedTextContents::{Expr, Text};
             ser.collect_seq(once(tag(18)).chain(xs.iter().map(|x| match x {
                 Expr(x) => expr(x),
-                Text(x) => cbor(String(x.clone())),
+                Text(x) => cbor(cbor::Value::Text(x.clone())),
             })))
         }
         RecordType(map) => ser_seq!(ser; tag(7), RecordMap(map)),
@@ -499,7 +578,7 @@ where
                 RecursiveRecordTypeMerge => 10,
                 ImportAlt => 11,
             };
-            ser_seq!(ser; tag(3), U64(op), expr(x), expr(y))
+            ser_seq!(ser; tag(3), Integer(op), expr(x), expr(y))
         }
         Merge(x, y, None) => ser_seq!(ser; tag(6), expr(x), expr(y)),
         Merge(x, y, Some(z)) => {
@@ -510,7 +589,7 @@ where
                 .chain(once(expr(x)))
                 .chain(ls.iter().map(label)),
         ),
-        Embed(import) => serialize_import(ser, import),
+        Embed(x) => x.serialize_embed(ser),
     }
 }
 
@@ -518,7 +597,7 @@ fn serialize_import<S>(ser: S, import: &Import) -> Result<S::Ok, S::Error>
 where
     S: serde::ser::Serializer,
 {
-    use cbor::Value::{Array, Null, String, U64};
+    use cbor::Value::{Array, Integer, Null, Text};
     use serde::ser::SerializeSeq;
 
     let count = 4 + match &import.location_hashed.location {
@@ -529,12 +608,12 @@ where
     };
     let mut ser_seq = ser.serialize_seq(Some(count))?;
 
-    ser_seq.serialize_element(&U64(24))?;
+    ser_seq.serialize_element(&Integer(24))?;
 
     let hash = match &import.location_hashed.hash {
         None => Null,
         Some(h) => {
-            Array(vec![String(h.protocol.clone()), String(h.hash.clone())])
+            Array(vec![Text(h.protocol.clone()), Text(h.hash.clone())])
         }
     };
     ser_seq.serialize_element(&hash)?;
@@ -543,7 +622,7 @@ where
         ImportMode::Code => 0,
         ImportMode::RawText => 1,
     };
-    ser_seq.serialize_element(&U64(mode))?;
+    ser_seq.serialize_element(&Integer(mode))?;
 
     let scheme = match &import.location_hashed.location {
         ImportLocation::Remote(url) => match url.scheme {
@@ -559,14 +638,14 @@ where
         ImportLocation::Env(_) => 6,
         ImportLocation::Missing => 7,
     };
-    ser_seq.serialize_element(&U64(scheme))?;
+    ser_seq.serialize_element(&Integer(scheme))?;
 
     match &import.location_hashed.location {
         ImportLocation::Remote(url) => {
             match &url.headers {
                 None => ser_seq.serialize_element(&Null)?,
                 Some(location_hashed) => ser_seq.serialize_element(
-                    &self::Serialize::Import(&Import {
+                    &self::Serialize::<X, X>::Import(&Import {
                         mode: ImportMode::Code,
                         location_hashed: location_hashed.as_ref().clone(),
                     }),
@@ -595,7 +674,7 @@ where
     ser_seq.end()
 }
 
-impl<'a> serde::ser::Serialize for Serialize<'a> {
+impl<'a, N, E: EncodeEmbed> serde::ser::Serialize for Serialize<'a, N, E> {
     fn serialize<S>(&self, ser: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
@@ -605,7 +684,7 @@ impl<'a> serde::ser::Serialize for Serialize<'a> {
             Serialize::CBOR(v) => v.serialize(ser),
             Serialize::RecordMap(map) => {
                 ser.collect_map(map.iter().map(|(k, v)| {
-                    (cbor::Value::String(k.into()), Serialize::Expr(v))
+                    (cbor::Value::Text(k.into()), Serialize::Expr(v))
                 }))
             }
             Serialize::UnionMap(map) => {
@@ -614,7 +693,7 @@ impl<'a> serde::ser::Serialize for Serialize<'a> {
                         Some(x) => Serialize::Expr(x),
                         None => Serialize::CBOR(cbor::Value::Null),
                     };
-                    (cbor::Value::String(k.into()), v)
+                    (cbor::Value::Text(k.into()), v)
                 }))
             }
             Serialize::Import(import) => serialize_import(ser, import),
@@ -641,3 +720,51 @@ fn collect_nested_applications<'a, N, E>(
     let e = go(e, &mut vec);
     (e, vec)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cbor::Value::{Array, Integer, Text};
+
+    fn decode_value(v: cbor::Value) -> Result<DecodedSubExpr, DecodeError> {
+        decode(&serde_cbor::ser::to_vec(&v).unwrap())
+    }
+
+    #[test]
+    fn errors_have_a_path() {
+        // `λ(x : Bool) → [ x, Foo ]`
+        let v = Array(vec![
+            Integer(1),
+            Text("x".into()),
+            Text("Bool".into()),
+            Array(vec![
+                Integer(4),
+                cbor::Value::Null,
+                Integer(0),
+                Text("Foo".into()),
+            ]),
+        ]);
+        let err = decode_value(v).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid builtin at $[3][3]");
+    }
+
+    #[test]
+    fn rejects_non_canonical_encodings() {
+        let underscore_var = Array(vec![Text("_".into()), Integer(0)]);
+        let small_bignum = Array(vec![
+            Integer(15),
+            cbor::Value::Tag(2, Box::new(cbor::Value::Bytes(vec![1]))),
+        ]);
+        let nested_app = Array(vec![
+            Integer(0),
+            Array(vec![Integer(0), Integer(0), Integer(1)]),
+            Integer(2),
+        ]);
+        for v in vec![underscore_var, small_bignum, nested_app] {
+            match decode_value(v) {
+                Err(DecodeError::NonCanonical(_, _)) => {}
+                r => panic!("Expected a non-canonical error, got: {:?}", r),
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::borrow::Cow;
 use std::fmt::Display;
 use std::path::Path;
 
-use dhall_syntax::{Const, Import, Span, SubExpr, X};
+use dhall_syntax::{Const, Hash, Import, Span, SubExpr, X};
 
 use crate::core::context::TypecheckContext;
 use crate::core::thunk::Thunk;
@@ -75,6 +75,14 @@ impl Parsed {
     pub fn skip_resolve(self) -> Result<Resolved, ImportError> {
         resolve::skip_resolve_expr(self)
     }
+    /// Adds the semantic hash of what they point to to the imports that can
+    /// be resolved.
+    pub fn freeze(self) -> Result<Parsed, ImportError> {
+        resolve::freeze(self)
+    }
+    pub fn to_expr(&self) -> ParsedSubExpr {
+        self.0.clone()
+    }
 
     #[allow(dead_code)]
     pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
@@ -94,6 +102,14 @@ impl Resolved {
     pub fn skip_typecheck(self) -> Typed {
         typecheck::skip_typecheck(self)
     }
+    pub fn to_expr(&self) -> ResolvedSubExpr {
+        self.0.clone()
+    }
+    /// Wraps an expression built without parsing, e.g. from another data
+    /// format. It must contain no imports to begin with.
+    pub(crate) fn from_normalized_expr(e: NormalizedSubExpr) -> Self {
+        Resolved(e.absurd())
+    }
 }
 
 impl Typed {
@@ -144,6 +160,16 @@ impl Typed {
     pub fn to_expr_alpha(&self) -> NormalizedSubExpr {
         self.to_value().normalize_to_expr_maybe_alpha(true)
     }
+    /// Encodes the normal form of the expression to CBOR.
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        crate::phase::binary::encode(&self.to_expr())
+    }
+    pub fn as_thunk(&self) -> Option<&Thunk> {
+        match self {
+            Typed::Untyped(th) | Typed::Typed(th, _) => Some(th),
+            Typed::Const(_) => None,
+        }
+    }
     pub fn to_thunk(&self) -> Thunk {
         match self {
             Typed::Untyped(th) | Typed::Typed(th, _) => th.clone(),
@@ -189,6 +215,25 @@ impl Typed {
 }
 
 impl Normalized {
+    /// Encodes the expression to CBOR. Since it is resolved, the result does
+    /// not depend on any import.
+    pub fn encode(&self) -> Result<Vec<u8>, EncodeError> {
+        self.0.encode()
+    }
+    /// Encodes the alpha-normal form of the expression, as used for semantic
+    /// hashes.
+    pub fn encode_alpha(&self) -> Result<Vec<u8>, EncodeError> {
+        crate::phase::binary::encode(&self.to_expr_alpha())
+    }
+    /// The SHA-256 of [Normalized::encode_alpha], as used to protect imports.
+    pub fn semantic_hash(&self) -> Result<Hash, EncodeError> {
+        use sha2::Digest;
+        let digest = sha2::Sha256::digest(&self.encode_alpha()?);
+        Ok(Hash {
+            protocol: "sha256".to_owned(),
+            hash: digest.iter().map(|b| format!("{:02x}", b)).collect(),
+        })
+    }
     #[allow(dead_code)]
     pub fn to_expr(&self) -> NormalizedSubExpr {
         self.0.to_expr()
@@ -269,7 +314,12 @@ derive_traits_for_wrapper_struct!(Normalized);
 impl Eq for Typed {}
 impl PartialEq for Typed {
     fn eq(&self, other: &Self) -> bool {
-        self.to_value() == other.to_value()
+        match (self.as_thunk(), other.as_thunk()) {
+            // Short-circuits when both share the same thunk, which interning
+            // makes common for types.
+            (Some(x), Some(y)) => x == y,
+            _ => self.to_value() == other.to_value(),
+        }
     }
 }
 
@@ -1,11 +1,14 @@
 use std::collections::HashMap;
 
+use num_traits::ToPrimitive;
+
 use dhall_syntax::{
-    BinOp, Builtin, ExprF, InterpolatedText, InterpolatedTextContents,
-    NaiveDouble, X,
+    escape_text, BinOp, Builtin, ExprF, Integer, InterpolatedTextContents,
+    NaiveDouble, Natural, X,
 };
 
 use crate::core::context::NormalizationContext;
+use crate::core::limits::{self, DepthGuard};
 use crate::core::thunk::{Thunk, TypeThunk};
 use crate::core::value::Value;
 use crate::core::var::Subst;
@@ -18,6 +21,7 @@ pub type OutputSubExpr = NormalizedSubExpr;
 pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
     use dhall_syntax::Builtin::*;
     use Value::*;
+    limits::step();
 
     // Return Ok((unconsumed args, returned value)), or Err(()) if value could not be produced.
     let ret = match (b, args.as_slice()) {
@@ -25,19 +29,19 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
             Ok((r, EmptyOptionalLit(TypeThunk::from_thunk(t.clone()))))
         }
         (NaturalIsZero, [n, r..]) => match &*n.as_value() {
-            NaturalLit(n) => Ok((r, BoolLit(*n == 0))),
+            NaturalLit(n) => Ok((r, BoolLit(n.is_zero()))),
             _ => Err(()),
         },
         (NaturalEven, [n, r..]) => match &*n.as_value() {
-            NaturalLit(n) => Ok((r, BoolLit(*n % 2 == 0))),
+            NaturalLit(n) => Ok((r, BoolLit(n.is_even()))),
             _ => Err(()),
         },
         (NaturalOdd, [n, r..]) => match &*n.as_value() {
-            NaturalLit(n) => Ok((r, BoolLit(*n % 2 != 0))),
+            NaturalLit(n) => Ok((r, BoolLit(!n.is_even()))),
             _ => Err(()),
         },
         (NaturalToInteger, [n, r..]) => match &*n.as_value() {
-            NaturalLit(n) => Ok((r, IntegerLit(*n as isize))),
+            NaturalLit(n) => Ok((r, IntegerLit(Integer::from(n.clone())))),
             _ => Err(()),
         },
         (NaturalShow, [n, r..]) => match &*n.as_value() {
@@ -49,7 +53,7 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
         },
         (IntegerShow, [n, r..]) => match &*n.as_value() {
             IntegerLit(n) => {
-                let s = if *n < 0 {
+                let s = if n.is_negative() {
                     n.to_string()
                 } else {
                     format!("+{}", n)
@@ -59,7 +63,15 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
             _ => Err(()),
         },
         (IntegerToDouble, [n, r..]) => match &*n.as_value() {
-            IntegerLit(n) => Ok((r, DoubleLit(NaiveDouble::from(*n as f64)))),
+            IntegerLit(n) => {
+                // Integers too big for a Double round to an infinity
+                let x = n.to_f64().unwrap_or(if n.is_negative() {
+                    std::f64::NEG_INFINITY
+                } else {
+                    std::f64::INFINITY
+                });
+                Ok((r, DoubleLit(NaiveDouble::from(x))))
+            }
             _ => Err(()),
         },
         (DoubleShow, [n, r..]) => match &*n.as_value() {
@@ -75,25 +87,27 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
                     // If there are no interpolations (invariants ensure that when there are no
                     // interpolations, there is a single Text item) in the literal.
                     [InterpolatedTextContents::Text(s)] => {
-                        // Printing InterpolatedText takes care of all the escaping
-                        let txt: InterpolatedText<X> = std::iter::once(
-                            InterpolatedTextContents::Text(s.clone()),
-                        )
-                        .collect();
-                        let s = txt.to_string();
+                        let s = format!("\"{}\"", escape_text(s));
+                        limits::check_text_length(s.len());
                         Ok((
                             r,
                             TextLit(vec![InterpolatedTextContents::Text(s)]),
                         ))
                     }
+                    [] => Ok((
+                        r,
+                        TextLit(vec![InterpolatedTextContents::Text(
+                            "\"\"".to_owned(),
+                        )]),
+                    )),
                     _ => Err(()),
                 }
             }
             _ => Err(()),
         },
         (ListLength, [_, l, r..]) => match &*l.as_value() {
-            EmptyListLit(_) => Ok((r, NaturalLit(0))),
-            NEListLit(xs) => Ok((r, NaturalLit(xs.len()))),
+            EmptyListLit(_) => Ok((r, NaturalLit(Natural::from(0u64)))),
+            NEListLit(xs) => Ok((r, NaturalLit(Natural::from(xs.len())))),
             _ => Err(()),
         },
         (ListHead, [_, l, r..]) => match &*l.as_value() {
@@ -132,7 +146,7 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
                     .iter()
                     .enumerate()
                     .map(|(i, e)| {
-                        let i = NaturalLit(i);
+                        let i = NaturalLit(Natural::from(i));
                         let mut kvs = HashMap::new();
                         kvs.insert("index".into(), Thunk::from_value(i));
                         kvs.insert("value".into(), e.clone());
@@ -145,13 +159,8 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
         },
         (ListBuild, [t, f, r..]) => match &*f.as_value() {
             // fold/build fusion
-            Value::AppliedBuiltin(ListFold, args) => {
-                if args.len() >= 2 {
-                    Ok((r, args[1].to_value()))
-                } else {
-                    // Do we really need to handle this case ?
-                    unimplemented!()
-                }
+            Value::AppliedBuiltin(ListFold, args) if args.len() >= 2 => {
+                Ok((r, args[1].to_value()))
             }
             _ => Ok((
                 r,
@@ -180,13 +189,8 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
         },
         (OptionalBuild, [t, f, r..]) => match &*f.as_value() {
             // fold/build fusion
-            Value::AppliedBuiltin(OptionalFold, args) => {
-                if args.len() >= 2 {
-                    Ok((r, args[1].to_value()))
-                } else {
-                    // Do we really need to handle this case ?
-                    unimplemented!()
-                }
+            Value::AppliedBuiltin(OptionalFold, args) if args.len() >= 2 => {
+                Ok((r, args[1].to_value()))
             }
             _ => Ok((
                 r,
@@ -206,23 +210,18 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
         },
         (NaturalBuild, [f, r..]) => match &*f.as_value() {
             // fold/build fusion
-            Value::AppliedBuiltin(NaturalFold, args) => {
-                if !args.is_empty() {
-                    Ok((r, args[0].to_value()))
-                } else {
-                    // Do we really need to handle this case ?
-                    unimplemented!()
-                }
+            Value::AppliedBuiltin(NaturalFold, args) if !args.is_empty() => {
+                Ok((r, args[0].to_value()))
             }
             _ => Ok((
                 r,
                 f.app_val(Value::from_builtin(Natural))
                     .app_val(NaturalSuccClosure)
-                    .app_val(NaturalLit(0)),
+                    .app_val(NaturalLit(Natural::from(0u64))),
             )),
         },
         (NaturalFold, [n, t, succ, zero, r..]) => match &*n.as_value() {
-            NaturalLit(0) => Ok((r, zero.to_value())),
+            NaturalLit(n) if n.is_zero() => Ok((r, zero.to_value())),
             NaturalLit(n) => {
                 let fold = Value::from_builtin(NaturalFold)
                     .app(NaturalLit(n - 1))
@@ -248,6 +247,8 @@ pub fn apply_builtin(b: Builtin, args: Vec<Thunk>) -> Value {
 }
 
 pub fn apply_any(f: Thunk, a: Thunk) -> Value {
+    limits::step();
+    let _depth = DepthGuard::enter();
     let fallback = |f: Thunk, a: Thunk| Value::PartialExpr(ExprF::App(f, a));
 
     let f_borrow = f.as_value();
@@ -271,6 +272,7 @@ pub fn apply_any(f: Thunk, a: Thunk) -> Value {
                 Value::EmptyListLit(_) => Value::NEListLit(vec![x.clone()]),
                 Value::NEListLit(xs) => {
                     use std::iter::once;
+                    limits::check_list_length(xs.len() + 1);
                     let xs =
                         once(x.clone()).chain(xs.iter().cloned()).collect();
                     Value::NEListLit(xs)
@@ -312,16 +314,22 @@ pub fn squash_textlit(
     fn inner(
         elts: impl Iterator<Item = InterpolatedTextContents<Thunk>>,
         crnt_str: &mut String,
+        len: &mut usize,
         ret: &mut Vec<InterpolatedTextContents<Thunk>>,
     ) {
         for contents in elts {
             match contents {
-                Text(s) => crnt_str.push_str(&s),
+                Text(s) => {
+                    crnt_str.push_str(&s);
+                    // Count the text around interpolations too
+                    *len += s.len();
+                    limits::check_text_length(*len);
+                }
                 Expr(e) => {
                     let e_borrow = e.as_value();
                     match &*e_borrow {
                         Value::TextLit(elts2) => {
-                            inner(elts2.iter().cloned(), crnt_str, ret)
+                            inner(elts2.iter().cloned(), crnt_str, len, ret)
                         }
                         _ => {
                             drop(e_borrow);
@@ -337,8 +345,9 @@ pub fn squash_textlit(
     }
 
     let mut crnt_str = String::new();
+    let mut len = 0;
     let mut ret = Vec::new();
-    inner(elts, &mut crnt_str, &mut ret);
+    inner(elts, &mut crnt_str, &mut len, &mut ret);
     if !crnt_str.is_empty() {
         ret.push(Text(replace(&mut crnt_str, String::new())))
     }
@@ -429,15 +438,19 @@ fn apply_binop<'a>(o: BinOp, x: &'a Thunk, y: &'a Thunk) -> Option<Ret<'a>> {
         (BoolNE, BoolLit(x), BoolLit(y)) => Ret::Value(BoolLit(x != y)),
         (BoolNE, _, _) if x == y => Ret::Value(BoolLit(false)),
 
-        (NaturalPlus, NaturalLit(0), _) => Ret::ThunkRef(y),
-        (NaturalPlus, _, NaturalLit(0)) => Ret::ThunkRef(x),
+        (NaturalPlus, NaturalLit(n), _) if n.is_zero() => Ret::ThunkRef(y),
+        (NaturalPlus, _, NaturalLit(n)) if n.is_zero() => Ret::ThunkRef(x),
         (NaturalPlus, NaturalLit(x), NaturalLit(y)) => {
             Ret::Value(NaturalLit(x + y))
         }
-        (NaturalTimes, NaturalLit(0), _) => Ret::Value(NaturalLit(0)),
-        (NaturalTimes, _, NaturalLit(0)) => Ret::Value(NaturalLit(0)),
-        (NaturalTimes, NaturalLit(1), _) => Ret::ThunkRef(y),
-        (NaturalTimes, _, NaturalLit(1)) => Ret::ThunkRef(x),
+        (NaturalTimes, NaturalLit(n), _) if n.is_zero() => {
+            Ret::Value(NaturalLit(Natural::from(0u64)))
+        }
+        (NaturalTimes, _, NaturalLit(n)) if n.is_zero() => {
+            Ret::Value(NaturalLit(Natural::from(0u64)))
+        }
+        (NaturalTimes, NaturalLit(n), _) if n.is_one() => Ret::ThunkRef(y),
+        (NaturalTimes, _, NaturalLit(n)) if n.is_one() => Ret::ThunkRef(x),
         (NaturalTimes, NaturalLit(x), NaturalLit(y)) => {
             Ret::Value(NaturalLit(x * y))
         }
@@ -445,6 +458,7 @@ fn apply_binop<'a>(o: BinOp, x: &'a Thunk, y: &'a Thunk) -> Option<Ret<'a>> {
         (ListAppend, EmptyListLit(_), _) => Ret::ThunkRef(y),
         (ListAppend, _, EmptyListLit(_)) => Ret::ThunkRef(x),
         (ListAppend, NEListLit(xs), NEListLit(ys)) => {
+            limits::check_list_length(xs.len() + ys.len());
             Ret::Value(NEListLit(xs.iter().chain(ys.iter()).cloned().collect()))
         }
 
@@ -527,6 +541,8 @@ pub fn normalize_one_layer(expr: ExprF<Thunk, X>) -> Value {
         NEListLit, NEOptionalLit, NaturalLit, Pi, RecordLit, RecordType,
         TextLit, UnionConstructor, UnionLit, UnionType,
     };
+    limits::step();
+    let _depth = DepthGuard::enter();
 
     let ret = match expr {
         ExprF::Embed(_) => unreachable!(),
@@ -2,16 +2,26 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-use dhall_syntax::parse_expr;
+use dhall_syntax::{parse_expr, ExprF, SubExpr};
 
 use crate::error::Error;
 use crate::phase::resolve::ImportRoot;
-use crate::phase::Parsed;
+use crate::phase::{DecodedSubExpr, Parsed};
 
 pub fn parse_file(f: &Path) -> Result<Parsed, Error> {
     let mut buffer = String::new();
     File::open(f)?.read_to_string(&mut buffer)?;
-    let expr = parse_expr(&*buffer)?;
+    let expr = parse_expr(&*buffer)
+        .map_err(|e| e.with_file_name(&f.to_string_lossy()))?;
+    let root = ImportRoot::LocalDir(f.parent().unwrap().to_owned());
+    Ok(Parsed(expr, root))
+}
+
+/// Reads the file at `f` as a Text literal, for an `as Text` import.
+pub fn parse_text_file(f: &Path) -> Result<Parsed, Error> {
+    let mut buffer = String::new();
+    File::open(f)?.read_to_string(&mut buffer)?;
+    let expr = SubExpr::from_expr_no_note(ExprF::TextLit(buffer.into()));
     let root = ImportRoot::LocalDir(f.parent().unwrap().to_owned());
     Ok(Parsed(expr, root))
 }
@@ -24,6 +34,12 @@ pub fn parse_str(s: &str) -> Result<Parsed, Error> {
 
 pub fn parse_binary(data: &[u8]) -> Result<Parsed, Error> {
     let expr = crate::phase::binary::decode(data)?;
+    from_decoded(expr)
+}
+
+/// Wraps an expression that was not parsed from a file, e.g. a decoded or
+/// generated one. Imports are relative to the current directory.
+pub fn from_decoded(expr: DecodedSubExpr) -> Result<Parsed, Error> {
     let root = ImportRoot::LocalDir(std::env::current_dir()?);
     Ok(Parsed(expr.note_absurd(), root))
 }
@@ -1,10 +1,13 @@
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 
-use dhall_syntax::Import;
+use dhall_syntax::{Import, ImportMode};
 
+#[cfg(feature = "sync")]
+use crate::core::limits::Evaluation;
 use crate::error::{Error, ImportError};
-use crate::phase::{Normalized, Parsed, Resolved};
+use crate::phase::parse::parse_text_file;
+use crate::phase::{Normalized, Parsed, ParsedSubExpr, Resolved};
 
 /// A root from which to resolve relative imports.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -12,49 +15,243 @@ pub enum ImportRoot {
     LocalDir(PathBuf),
 }
 
-type ImportCache = HashMap<Import, Normalized>;
-
 pub type ImportStack = Vec<Import>;
 
-fn resolve_import(
-    import: &Import,
-    root: &ImportRoot,
-    import_cache: &mut ImportCache,
-    import_stack: &ImportStack,
-) -> Result<Normalized, ImportError> {
+/// What an import points to: a file, and how to read it. The same file
+/// imported as code and as text gives two different expressions.
+type ImportKey = (PathBuf, ImportMode);
+
+/// The state shared by all the imports of one resolution, keyed by the file
+/// each import points to and its import mode.
+///
+/// Resolution happens in three steps. First every reachable file is read and
+/// parsed, one layer of imports at a time, with the files of a layer handled
+/// concurrently. Then the files whose imports have all been loaded are
+/// resolved, typechecked and normalized concurrently, bottom-up. Each file
+/// appears only once in the cache, so the same import is never loaded twice.
+/// Finally the root expression is resolved sequentially: this hits the cache
+/// for everything that was loaded successfully, and handles the remaining
+/// files (those that failed, or that take part in an import cycle) exactly
+/// like a plain depth-first resolution would. This makes the reported error
+/// independent of the order in which the threads finished.
+#[derive(Default)]
+struct ImportCache {
+    /// Files that have been parsed but not loaded yet.
+    parsed: HashMap<ImportKey, Result<Parsed, Error>>,
+    /// Files that have been successfully loaded.
+    loaded: HashMap<ImportKey, Normalized>,
+    /// Files whose imports were all loaded, but that failed to typecheck.
+    failed: HashMap<ImportKey, Error>,
+}
+
+/// Maps `f` over `xs`, concurrently when the `sync` feature makes expressions
+/// thread-safe. Each item takes part in the evaluation of the calling thread,
+/// so its limits apply and the steps of all items count towards one budget.
+#[cfg(feature = "sync")]
+fn par_map<T: Sync, U: Send>(
+    xs: &[T],
+    f: impl Fn(&T) -> U + Send + Sync,
+) -> Vec<U> {
+    use rayon::prelude::*;
+    let evaluation = Evaluation::current();
+    xs.par_iter().map(|x| evaluation.join(|| f(x))).collect()
+}
+
+#[cfg(not(feature = "sync"))]
+fn par_map<T, U>(xs: &[T], f: impl Fn(&T) -> U) -> Vec<U> {
+    xs.iter().map(f).collect()
+}
+
+/// The file a local import points to, if it can be found without any IO.
+fn import_key(import: &Import, root: &ImportRoot) -> Option<ImportKey> {
     use self::ImportRoot::*;
     use dhall_syntax::FilePrefix::*;
     use dhall_syntax::ImportLocation::*;
     let cwd = match root {
         LocalDir(cwd) => cwd,
     };
-    match &import.location_hashed.location {
+    let path = match &import.location_hashed.location {
         Local(prefix, path) => {
             let path: PathBuf = path.iter().cloned().collect();
-            let path = match prefix {
-                // TODO: fail gracefully
-                Parent => cwd.parent().unwrap().join(path),
+            match prefix {
+                Parent => cwd.parent()?.join(path),
                 Here => cwd.join(path),
-                _ => unimplemented!("{:?}", import),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some((path, import.mode))
+}
+
+/// Reads the file an import points to.
+fn read_import((f, mode): &ImportKey) -> Result<Parsed, Error> {
+    match mode {
+        ImportMode::Code => Parsed::parse_file(f),
+        ImportMode::RawText => parse_text_file(f),
+    }
+}
+
+/// The files directly imported by an expression.
+fn imported_keys(expr: &ParsedSubExpr, root: &ImportRoot) -> Vec<ImportKey> {
+    let mut keys = Vec::new();
+    let _: Result<_, !> = expr.traverse_embed(|import| {
+        keys.extend(import_key(import, root));
+        Ok(())
+    });
+    keys
+}
+
+impl ImportCache {
+    /// Parses every file reachable from `expr`.
+    fn parse_reachable(&mut self, expr: &ParsedSubExpr, root: &ImportRoot) {
+        let mut seen = HashSet::new();
+        let mut layer: Vec<ImportKey> = imported_keys(expr, root)
+            .into_iter()
+            .filter(|key| seen.insert(key.clone()))
+            .collect();
+        while !layer.is_empty() {
+            let results = par_map(&layer, read_import);
+            let mut next_layer = Vec::new();
+            for (f, parsed) in layer.into_iter().zip(results) {
+                if let Ok(Parsed(expr, root)) = &parsed {
+                    next_layer.extend(
+                        imported_keys(expr, root)
+                            .into_iter()
+                            .filter(|key| seen.insert(key.clone())),
+                    );
+                }
+                self.parsed.insert(f, parsed);
+            }
+            layer = next_layer;
+        }
+    }
+
+    /// Whether all the imports of `expr` have been loaded successfully.
+    fn all_loaded(&self, expr: &ParsedSubExpr, root: &ImportRoot) -> bool {
+        let mut all_loaded = true;
+        let _: Result<_, !> = expr.traverse_embed(|import| {
+            all_loaded &= match import_key(import, root) {
+                Some(f) => self.loaded.contains_key(&f),
+                None => false,
             };
-            Ok(load_import(&path, import_cache, import_stack).map_err(|e| {
-                ImportError::Recursive(import.clone(), Box::new(e))
-            })?)
+            Ok(())
+        });
+        all_loaded
+    }
+
+    /// Loads the parsed files bottom-up, as long as some file has all of its
+    /// imports loaded already.
+    fn load_parsed(&mut self) {
+        loop {
+            let ready: Vec<ImportKey> = self
+                .parsed
+                .iter()
+                .filter_map(|(f, parsed)| match parsed {
+                    Ok(Parsed(expr, root)) if self.all_loaded(expr, root) => {
+                        Some(f.clone())
+                    }
+                    _ => None,
+                })
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            let ready: Vec<(ImportKey, Parsed)> = ready
+                .into_iter()
+                .filter_map(|f| {
+                    let parsed = self.parsed.remove(&f)?.ok()?;
+                    Some((f, parsed))
+                })
+                .collect();
+
+            let loaded = &self.loaded;
+            let results =
+                par_map(&ready, |(_, parsed)| load_parsed_file(parsed, loaded));
+
+            for ((f, _), result) in ready.into_iter().zip(results) {
+                match result {
+                    Ok(expr) => {
+                        self.loaded.insert(f, expr);
+                    }
+                    Err(e) => {
+                        self.failed.insert(f, e);
+                    }
+                }
+            }
         }
-        _ => unimplemented!("{:?}", import),
     }
 }
 
+/// Checks that the expression an import points to has the hash the import
+/// expects, if any.
+fn check_hash(import: &Import, expr: &Normalized) -> Result<(), ImportError> {
+    let expected = match &import.location_hashed.hash {
+        Some(expected) => expected,
+        None => return Ok(()),
+    };
+    let actual = expr
+        .semantic_hash()
+        .map_err(|e| ImportError::Hash(import.clone(), e))?;
+    if &actual != expected {
+        return Err(ImportError::HashMismatch(import.clone(), actual));
+    }
+    Ok(())
+}
+
+/// Loads a file whose imports have all been loaded already.
+fn load_parsed_file(
+    Parsed(expr, root): &Parsed,
+    loaded: &HashMap<ImportKey, Normalized>,
+) -> Result<Normalized, Error> {
+    let expr =
+        expr.traverse_embed(|import| match import_key(import, root) {
+            Some(f) => {
+                let expr = loaded[&f].clone();
+                check_hash(import, &expr)?;
+                Ok(expr)
+            }
+            None => Err(ImportError::UnexpectedImport(import.clone())),
+        })?;
+    Ok(Resolved(expr).typecheck()?.normalize())
+}
+
+fn resolve_import(
+    import: &Import,
+    root: &ImportRoot,
+    import_cache: &mut ImportCache,
+    import_stack: &ImportStack,
+) -> Result<Normalized, ImportError> {
+    let key = match import_key(import, root) {
+        Some(key) => key,
+        None => return Err(ImportError::Unsupported(import.clone())),
+    };
+    let expr = load_import(&key, import_cache, import_stack)
+        .map_err(|e| ImportError::Recursive(import.clone(), Box::new(e)))?;
+    check_hash(import, &expr)?;
+    Ok(expr)
+}
+
 fn load_import(
-    f: &Path,
+    key: &ImportKey,
     import_cache: &mut ImportCache,
     import_stack: &ImportStack,
 ) -> Result<Normalized, Error> {
-    Ok(
-        do_resolve_expr(Parsed::parse_file(f)?, import_cache, import_stack)?
-            .typecheck()?
-            .normalize(),
-    )
+    if let Some(expr) = import_cache.loaded.get(key) {
+        return Ok(expr.clone());
+    }
+    if let Some(e) = import_cache.failed.remove(key) {
+        return Err(e);
+    }
+    let parsed = match import_cache.parsed.remove(key) {
+        Some(parsed) => parsed?,
+        None => read_import(key)?,
+    };
+    let expr = do_resolve_expr(parsed, import_cache, import_stack)?
+        .typecheck()?
+        .normalize();
+    import_cache.loaded.insert(key.clone(), expr.clone());
+    Ok(expr)
 }
 
 fn do_resolve_expr(
@@ -69,29 +266,22 @@ fn do_resolve_expr(
                 import.clone(),
             ));
         }
-        match import_cache.get(import) {
-            Some(expr) => Ok(expr.clone()),
-            None => {
-                // Copy the import stack and push the current import
-                let mut import_stack = import_stack.clone();
-                import_stack.push(import.clone());
-
-                // Resolve the import recursively
-                let expr =
-                    resolve_import(import, &root, import_cache, &import_stack)?;
-
-                // Add the import to the cache
-                import_cache.insert(import.clone(), expr.clone());
-                Ok(expr)
-            }
-        }
+        // Copy the import stack and push the current import
+        let mut import_stack = import_stack.clone();
+        import_stack.push(import.clone());
+
+        // Resolve the import recursively
+        resolve_import(import, &root, import_cache, &import_stack)
     };
     let expr = expr.traverse_embed(resolve)?;
     Ok(Resolved(expr))
 }
 
 pub fn resolve(e: Parsed) -> Result<Resolved, ImportError> {
-    do_resolve_expr(e, &mut HashMap::new(), &Vec::new())
+    let mut import_cache = ImportCache::default();
+    import_cache.parse_reachable(&e.0, &e.1);
+    import_cache.load_parsed();
+    do_resolve_expr(e, &mut import_cache, &Vec::new())
 }
 
 pub fn skip_resolve_expr(
@@ -104,6 +294,119 @@ pub fn skip_resolve_expr(
     Ok(Resolved(expr))
 }
 
+/// Sets the hash of every import that can be resolved to the semantic hash of
+/// what it points to. Other imports are left as they are.
+pub fn freeze(e: Parsed) -> Result<Parsed, ImportError> {
+    let mut import_cache = ImportCache::default();
+    import_cache.parse_reachable(&e.0, &e.1);
+    import_cache.load_parsed();
+    let Parsed(expr, root) = e;
+    let expr = expr.traverse_embed(|import| -> Result<_, ImportError> {
+        if import_key(import, &root).is_none() {
+            return Ok(import.clone());
+        }
+        let import_stack = vec![import.clone()];
+        let hash =
+            resolve_import(import, &root, &mut import_cache, &import_stack)?
+                .semantic_hash()
+                .map_err(|e| ImportError::Hash(import.clone(), e))?;
+        let mut import = import.clone();
+        import.location_hashed.hash = Some(hash);
+        Ok(import)
+    })?;
+    Ok(Parsed(expr, root))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// Writes `files` to a fresh directory named after `test`.
+    fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "dhall-resolve-{}-{}",
+            test,
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, contents) in files {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+        dir
+    }
+
+    fn normalize(parsed: Parsed) -> Normalized {
+        parsed.resolve().unwrap().typecheck().unwrap().normalize()
+    }
+
+    #[test]
+    fn shared_imports_are_loaded_once() {
+        let dir = write_files(
+            "diamond",
+            &[
+                ("root", "./left + ./right"),
+                ("left", "./shared + 1"),
+                ("right", "./shared + 2"),
+                ("shared", "10"),
+            ],
+        );
+        let root = Parsed::parse_file(&dir.join("root")).unwrap();
+        let mut import_cache = ImportCache::default();
+        import_cache.parse_reachable(&root.0, &root.1);
+        assert_eq!(import_cache.parsed.len(), 3);
+        // Nothing gets read after this
+        fs::remove_dir_all(&dir).unwrap();
+        import_cache.load_parsed();
+        assert!(import_cache.parsed.is_empty());
+        assert_eq!(import_cache.loaded.len(), 3);
+        let resolved =
+            do_resolve_expr(root, &mut import_cache, &Vec::new()).unwrap();
+        assert_eq!(resolved.typecheck().unwrap().normalize().to_string(), "23");
+    }
+
+    #[test]
+    fn the_first_failing_import_is_reported() {
+        let dir = write_files(
+            "siblings",
+            &[
+                ("root", "[ ./x, ./y ]"),
+                ("x", "1 + True"),
+                ("y", "True && 1"),
+            ],
+        );
+        // The imports get loaded concurrently with the `sync` feature; the
+        // order in which they fail must not matter.
+        for _ in 0..20 {
+            let root = Parsed::parse_file(&dir.join("root")).unwrap();
+            match root.resolve() {
+                Err(ImportError::Recursive(import, _)) => {
+                    assert_eq!(import.to_string(), "./x")
+                }
+                _ => panic!("expected ./x to fail"),
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn import_modes_are_cached_separately() {
+        let dir = write_files(
+            "modes",
+            &[
+                ("root", "{ code = ./t, text = ./t as Text }"),
+                ("t", "1 + 1"),
+            ],
+        );
+        let root = Parsed::parse_file(&dir.join("root")).unwrap();
+        let expected =
+            Parsed::parse_str("{ code = 2, text = \"1 + 1\" }").unwrap();
+        assert_eq!(normalize(root), normalize(expected));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
 #[cfg(test)]
 mod spec_tests {
     #![rustfmt::skip]
@@ -0,0 +1,135 @@
+//! Loading a Dhall expression one phase at a time.
+//!
+//! [Value][crate::Value] and [dhall::de][crate::de] go through all the phases
+//! at once. This module gives access to the intermediate results, e.g. to
+//! print an expression without evaluating it, or to encode it.
+//!
+//! ```edition2018
+//! # fn main() -> dhall::error::Result<()> {
+//! use dhall::pipeline::Parsed;
+//!
+//! let typed = Parsed::parse_str("let x = 1 in x + 2")?.resolve()?.typecheck()?;
+//! assert_eq!(typed.get_type()?.to_string(), "Natural");
+//! assert_eq!(typed.normalize().to_string(), "3");
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use dhall_syntax::{Hash, Import, Span, SubExpr, X};
+
+use crate::error::Result;
+use crate::phase;
+
+/// A parsed expression, whose imports have not been resolved yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed(phase::Parsed);
+
+/// An expression whose imports have been replaced by the normal form of what
+/// they point to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved(phase::Resolved);
+
+/// A well-typed expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Typed(phase::Typed);
+
+/// An expression in normal form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Normalized(phase::Normalized);
+
+impl Parsed {
+    /// Parses the file at `f`. Relative imports are resolved relative to
+    /// that file.
+    pub fn parse_file(f: &Path) -> Result<Self> {
+        Ok(Parsed(phase::Parsed::parse_file(f)?))
+    }
+    /// Parses `s`. Relative imports are resolved relative to the current
+    /// directory.
+    pub fn parse_str(s: &str) -> Result<Self> {
+        Ok(Parsed(phase::Parsed::parse_str(s)?))
+    }
+    /// Like [Parsed::parse_file], for an expression encoded in CBOR.
+    pub fn parse_binary_file(f: &Path) -> Result<Self> {
+        Ok(Parsed(phase::Parsed::parse_binary_file(f)?))
+    }
+    /// Like [Parsed::parse_str], for an expression encoded in CBOR.
+    pub fn parse_binary(data: &[u8]) -> Result<Self> {
+        Ok(Parsed(phase::Parsed::parse_binary(data)?))
+    }
+
+    /// Loads the imports, recursively.
+    pub fn resolve(self) -> Result<Resolved> {
+        Ok(Resolved(self.0.resolve()?))
+    }
+    /// Resolves an expression that contains no imports, without touching the
+    /// filesystem. Fails if there is any import.
+    pub fn skip_resolve(self) -> Result<Resolved> {
+        Ok(Resolved(self.0.skip_resolve()?))
+    }
+    /// Adds the semantic hash of what they point to to the imports that can
+    /// be resolved.
+    pub fn freeze(self) -> Result<Parsed> {
+        Ok(Parsed(self.0.freeze()?))
+    }
+    /// Encodes the expression, imports included, to CBOR.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        Ok(self.0.encode()?)
+    }
+    pub fn to_expr(&self) -> SubExpr<Span, Import> {
+        self.0.to_expr()
+    }
+}
+
+impl Resolved {
+    pub fn typecheck(self) -> Result<Typed> {
+        Ok(Typed(self.0.typecheck()?))
+    }
+    /// The expression, with the normal form of each import in its place.
+    pub fn to_expr(&self) -> SubExpr<Span, Normalized> {
+        self.0.to_expr().map_embed(|n| Normalized(n.clone()))
+    }
+}
+
+impl Typed {
+    /// Evaluates the expression. Since it is well-typed, this cannot fail.
+    pub fn normalize(self) -> Normalized {
+        Normalized(self.0.normalize())
+    }
+    /// The type of the expression, in normal form.
+    pub fn get_type(&self) -> Result<Normalized> {
+        Ok(Normalized(self.0.get_type()?.to_normalized()))
+    }
+}
+
+impl Normalized {
+    /// Encodes the expression to CBOR. Since it is resolved, the result does
+    /// not depend on any import.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        Ok(self.0.encode()?)
+    }
+    /// The hash that protects an import of this expression, as in
+    /// `./file.dhall sha256:...`.
+    pub fn semantic_hash(&self) -> Result<Hash> {
+        Ok(self.0.semantic_hash()?)
+    }
+    pub fn to_expr(&self) -> SubExpr<X, X> {
+        self.0.to_expr()
+    }
+}
+
+macro_rules! derive_display {
+    ($ty:ident) => {
+        impl std::fmt::Display for $ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+derive_display!(Parsed);
+derive_display!(Resolved);
+derive_display!(Typed);
+derive_display!(Normalized);
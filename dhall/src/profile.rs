@@ -0,0 +1,84 @@
+//! Timing of the phases of loading a Dhall expression.
+//!
+//! ```edition2018
+//! # fn main() -> dhall::error::Result<()> {
+//! let profile = dhall::profile::profile_str("let x = 1 in x + 2")?;
+//! println!("{}", profile);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! To time the phases in finer detail, go through
+//! [dhall::pipeline][crate::pipeline] step by step.
+
+use std::fmt::{self, Display};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::error::Result;
+use crate::phase::Parsed;
+
+/// How long each phase took on one input.
+///
+/// Resolving imports involves loading the imported files, so the time spent
+/// parsing, typechecking and normalizing them counts towards `resolve`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Profile {
+    pub parse: Duration,
+    pub resolve: Duration,
+    pub typecheck: Duration,
+    pub normalize: Duration,
+}
+
+impl Profile {
+    pub fn total(&self) -> Duration {
+        self.parse + self.resolve + self.typecheck + self.normalize
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "parse: {:?}, resolve: {:?}, typecheck: {:?}, normalize: {:?}, \
+             total: {:?}",
+            self.parse,
+            self.resolve,
+            self.typecheck,
+            self.normalize,
+            self.total()
+        )
+    }
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let x = f();
+    (x, start.elapsed())
+}
+
+fn profile(parse: impl FnOnce() -> Result<Parsed>) -> Result<Profile> {
+    let (parsed, parse) = time(parse);
+    let parsed = parsed?;
+    let (resolved, resolve) = time(|| parsed.resolve());
+    let resolved = resolved?;
+    let (typed, typecheck) = time(|| resolved.typecheck());
+    let typed = typed?;
+    let (_, normalize) = time(|| typed.normalize());
+    Ok(Profile {
+        parse,
+        resolve,
+        typecheck,
+        normalize,
+    })
+}
+
+/// Loads a Dhall expression from a string, and times each phase.
+pub fn profile_str(s: &str) -> Result<Profile> {
+    profile(|| Parsed::parse_str(s))
+}
+
+/// Loads a Dhall expression from a file, and times each phase.
+pub fn profile_file(f: &Path) -> Result<Profile> {
+    profile(|| Parsed::parse_file(f))
+}
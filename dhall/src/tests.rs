@@ -37,7 +37,7 @@ use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 
-use crate::error::{Error, Result};
+use crate::error::{Error, ImportError, Result};
 use crate::phase::Parsed;
 
 #[derive(Copy, Clone)]
@@ -45,6 +45,7 @@ pub enum Feature {
     Parser,
     Printer,
     BinaryEncoding,
+    BinaryDecoding,
     Import,
     Normalization,
     AlphaNormalization,
@@ -84,6 +85,7 @@ pub fn run_test(
         Parser => "parser/",
         Printer => "parser/",
         BinaryEncoding => "parser/",
+        BinaryDecoding => "binary-decode/",
         Import => "import/",
         Normalization => "normalization/",
         AlphaNormalization => "alpha-normalization/",
@@ -94,6 +96,16 @@ pub fn run_test(
         "../dhall-lang/tests/".to_owned() + feature_prefix + base_path;
     match status {
         Success => {
+            if let BinaryDecoding = feature {
+                let expr_file_path = base_path.clone() + "A.dhallb";
+                let expr =
+                    Parsed::parse_binary_file(&PathBuf::from(&expr_file_path))?;
+                let expected = parse_file_str(&(base_path + "B.dhall"))?;
+                assert_eq_pretty!(expr, expected);
+
+                return Ok(());
+            }
+
             let expr_file_path = base_path.clone() + "A.dhall";
             let expr = parse_file_str(&expr_file_path)?;
 
@@ -164,7 +176,9 @@ pub fn run_test(
                 .normalize();
 
             match feature {
-                Parser | Printer | BinaryEncoding => unreachable!(),
+                Parser | Printer | BinaryEncoding | BinaryDecoding => {
+                    unreachable!()
+                }
                 Import => {
                     let expr = expr.skip_typecheck().normalize();
                     assert_eq_display!(expr, expected);
@@ -199,15 +213,29 @@ pub fn run_test(
                     }
                 }
                 Printer | BinaryEncoding => unreachable!(),
+                BinaryDecoding => {
+                    let file_path = PathBuf::from(file_path + "b");
+                    match Parsed::parse_binary_file(&file_path).unwrap_err() {
+                        Error::Decode(_) => {}
+                        e => panic!("Expected decode error, got: {:?}", e),
+                    }
+                }
                 Import => {
                     parse_file_str(&file_path)?.resolve().unwrap_err();
                 }
                 Normalization | AlphaNormalization => unreachable!(),
                 Typecheck | TypeInference => {
-                    parse_file_str(&file_path)?
-                        .skip_resolve()?
-                        .typecheck()
-                        .unwrap_err();
+                    match parse_file_str(&file_path)?.resolve() {
+                        Ok(expr) => {
+                            expr.typecheck().unwrap_err();
+                        }
+                        // Imported expressions are typechecked on their own
+                        Err(ImportError::Recursive(_, e)) => match *e {
+                            Error::Typecheck(_) => {}
+                            e => Err(e)?,
+                        },
+                        Err(e) => Err(e)?,
+                    }
                 }
             }
         }
@@ -219,3 +247,59 @@ mod spec {
     // See build.rs
     include!(concat!(env!("OUT_DIR"), "/spec_tests.rs"));
 }
+
+mod properties {
+    use proptest::prelude::*;
+
+    use crate::arbitrary::{expr, well_typed_expr, ArbExpr};
+    use crate::error::Result;
+    use crate::phase::parse::from_decoded;
+    use crate::phase::{binary, Normalized, Typed};
+
+    fn typecheck(e: &ArbExpr) -> Result<Typed> {
+        Ok(from_decoded(e.clone())?.skip_resolve()?.typecheck()?)
+    }
+
+    fn normalize(e: &ArbExpr) -> Result<Normalized> {
+        Ok(typecheck(e)?.normalize())
+    }
+
+    proptest! {
+        #[test]
+        fn parse_print(e in expr()) {
+            let printed = e.to_string();
+            let parsed = dhall_syntax::parse_expr(&printed).ok();
+            prop_assert_eq!(parsed, Some(e.note_absurd()), "{}", printed);
+        }
+
+        #[test]
+        fn decode_encode(e in expr()) {
+            let data = binary::encode(&e).unwrap();
+            prop_assert_eq!(binary::decode(&data).ok(), Some(e));
+        }
+
+        #[test]
+        fn decode_encode_normalized(e in well_typed_expr()) {
+            let normalized = normalize(&e).unwrap();
+            let data = normalized.encode().unwrap();
+            let decoded = binary::decode(&data).unwrap();
+            prop_assert_eq!(decoded, normalized.to_expr().absurd());
+        }
+
+        #[test]
+        fn normalize_idempotent(e in well_typed_expr()) {
+            let once = normalize(&e).unwrap().to_expr();
+            let twice = normalize(&once.absurd()).unwrap().to_expr();
+            prop_assert_eq!(once, twice);
+        }
+
+        #[test]
+        fn normalize_preserves_type(e in well_typed_expr()) {
+            let ty = typecheck(&e).unwrap().get_type().unwrap().to_expr();
+            let normalized = normalize(&e).unwrap().to_expr().absurd();
+            let normalized_ty =
+                typecheck(&normalized).unwrap().get_type().unwrap().to_expr();
+            prop_assert_eq!(ty, normalized_ty);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Machine-generated configs can be very deeply nested; none of the phases
+//! should overflow the stack on them.
+use dhall::de::from_str;
+use dhall_syntax::parse_expr;
+
+const DEPTH: usize = 100_000;
+
+#[test]
+fn deep_binop_chain() {
+    let mut s = String::from("0");
+    for _ in 0..DEPTH {
+        s.push_str(" + 1");
+    }
+    assert_eq!(from_str::<u64>(&s, None).unwrap(), DEPTH as u64);
+}
+
+#[test]
+fn long_let_chain() {
+    let mut s = String::from("let x0 = 0\n");
+    for i in 1..DEPTH {
+        s.push_str(&format!("let x{} = x{} + 1\n", i, i - 1));
+    }
+    s.push_str(&format!("in x{}", DEPTH - 1));
+    assert_eq!(from_str::<u64>(&s, None).unwrap(), DEPTH as u64 - 1);
+}
+
+#[test]
+fn print_deep_list_append() {
+    let s = (0..DEPTH)
+        .map(|i| format!("[{}]", i))
+        .collect::<Vec<_>>()
+        .join(" # ");
+    let e = parse_expr(&s).unwrap();
+    assert_eq!(parse_expr(&e.to_string()).unwrap(), e);
+}
@@ -0,0 +1,217 @@
+use dhall::de::{from_str, Type, Value};
+use dhall::json::{
+    from_json, from_yaml, to_json, to_json_with, to_yaml, JsonOptions,
+};
+use serde_json::json;
+
+fn dhall_to_json(s: &str) -> dhall::error::Result<serde_json::Value> {
+    to_json(&Value::from_str(s, None)?)
+}
+
+#[test]
+fn conventions() {
+    let s = r#"
+        let Union = < A : Natural | B | C : Text >
+        in  { record = { x = 1, y = -2, z = 0.5 }
+            , optional = [ Some True, None Bool ]
+            , unions = [ Union.A 1, Union.B, Union.C "c" ]
+            , map = [ { mapKey = "a", mapValue = 1 } ]
+            , emptyMap = [] : List { mapKey : Text, mapValue : Natural }
+            , emptyList = [] : List Natural
+            , none = None Natural
+            }
+    "#;
+    assert_eq!(
+        dhall_to_json(s).unwrap(),
+        json!({
+            "record": { "x": 1, "y": -2, "z": 0.5 },
+            "optional": [true, null],
+            "unions": [1, "B", "c"],
+            "map": { "a": 1 },
+            "emptyMap": {},
+            "emptyList": [],
+        })
+    );
+}
+
+#[test]
+fn prelude_json_type() {
+    let s = r#"
+        λ(JSON : Type) →
+        λ ( json
+          : { array : List JSON → JSON
+            , bool : Bool → JSON
+            , null : JSON
+            , number : Double → JSON
+            , object : List { mapKey : Text, mapValue : JSON } → JSON
+            , string : Text → JSON
+            }
+          ) →
+          json.object
+            [ { mapKey = "a", mapValue = json.array [ json.null, json.bool True ] }
+            , { mapKey = "b", mapValue = json.string "x" }
+            ]
+    "#;
+    assert_eq!(
+        dhall_to_json(s).unwrap(),
+        json!({ "a": [null, true], "b": "x" })
+    );
+}
+
+#[test]
+fn preserve_null() {
+    let v = Value::from_str("{ a = None Natural, b = [ None Bool ] }", None)
+        .unwrap();
+    let options = JsonOptions {
+        preserve_null: true,
+    };
+    assert_eq!(
+        to_json_with(&v, &options).unwrap(),
+        json!({ "a": null, "b": [null] })
+    );
+    assert_eq!(to_json(&v).unwrap(), json!({ "b": [null] }));
+}
+
+#[test]
+fn errors() {
+    let err =
+        dhall_to_json("{ x = [ 1 ], f = λ(x : Bool) → x }").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "At `.f`: Functions cannot be converted to JSON"
+    );
+    let err = dhall_to_json(
+        r#"{ m = [ { mapKey = "a", mapValue = 1 }, { mapKey = "a", mapValue = 2 } ] }"#,
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "At `.m[1]`: Duplicate key `a`");
+}
+
+#[test]
+fn yaml() {
+    let v = Value::from_str("{ a = [ 1, 2 ] }", None).unwrap();
+    let expected: serde_yaml::Value =
+        serde_yaml::from_str("a:\n  - 1\n  - 2\n").unwrap();
+    assert_eq!(to_yaml(&v).unwrap(), expected);
+}
+
+fn json_to_dhall(
+    json: serde_json::Value,
+    ty: &str,
+) -> dhall::error::Result<String> {
+    let ty: Type = from_str(ty, None)?;
+    Ok(from_json(&json, &ty)?.to_expr().to_string())
+}
+
+#[test]
+fn from_json_follows_the_type() {
+    let ty = r#"
+        { n : Natural
+        , i : Integer
+        , o : Optional Bool
+        , l : List Text
+        , u : List < A : Natural | B : Text | C >
+        , m : List { mapKey : Text, mapValue : Double }
+        }
+    "#;
+    let json = json!({
+        "n": 1,
+        "i": 1,
+        "l": [],
+        "u": [1, "b", "C"],
+        "m": { "a": 1.5 },
+    });
+    let expected = Value::from_str(
+        r#"
+        { n = 1
+        , i = +1
+        , o = None Bool
+        , l = [] : List Text
+        , u =
+            [ < A : Natural | B : Text | C >.A 1
+            , < A : Natural | B : Text | C >.B "b"
+            , < A : Natural | B : Text | C >.C
+            ]
+        , m = [ { mapKey = "a", mapValue = 1.5 } ]
+        }
+    "#,
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        json_to_dhall(json, ty).unwrap(),
+        expected.to_expr().to_string()
+    );
+}
+
+#[test]
+fn from_json_errors() {
+    let ty = "{ x : List Natural }";
+    let err = json_to_dhall(json!({ "x": [1, -1] }), ty).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "At `.x[1]`: Expected a value of type `Natural`, got `-1`"
+    );
+    let err = json_to_dhall(json!({}), ty).unwrap_err();
+    assert_eq!(err.to_string(), "At `.x`: Missing field");
+    let ty = "List < A : { a : Natural } | B : Text >";
+    let err = json_to_dhall(json!([{ "a": true }]), ty).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "At `[0].a`: Expected a value of type `Natural`, got `true`, for the \
+         alternative `A` of `< A : { a : Natural } | B : Text >`, which comes \
+         closest"
+    );
+}
+
+#[test]
+fn from_json_round_trips_prelude_json() {
+    let ty = r#"
+        ∀(JSON : Type) →
+        ∀ ( json
+          : { array : List JSON → JSON
+            , bool : Bool → JSON
+            , null : JSON
+            , number : Double → JSON
+            , object : List { mapKey : Text, mapValue : JSON } → JSON
+            , string : Text → JSON
+            }
+          ) →
+          JSON
+    "#;
+    let json = json!({ "a": [null, true, 1.5, "x"], "b": {}, "c": [] });
+    let ty: Type = from_str(ty, None).unwrap();
+    assert_eq!(to_json(&from_json(&json, &ty).unwrap()).unwrap(), json);
+}
+
+#[test]
+fn from_json_round_trips_typed_numbers() {
+    // The current Prelude type, which has no `natural` constructor
+    let ty = r#"
+        ∀(JSON : Type) →
+        ∀ ( json
+          : { array : List JSON → JSON
+            , bool : Bool → JSON
+            , double : Double → JSON
+            , integer : Integer → JSON
+            , null : JSON
+            , object : List { mapKey : Text, mapValue : JSON } → JSON
+            , string : Text → JSON
+            }
+          ) →
+          JSON
+    "#;
+    let json = json!({ "a": [null, true, 1.5, "x", -2, 3], "b": {} });
+    let ty: Type = from_str(ty, None).unwrap();
+    assert_eq!(to_json(&from_json(&json, &ty).unwrap()).unwrap(), json);
+}
+
+#[test]
+fn from_yaml_works_like_from_json() {
+    let ty: Type = from_str("{ a : List Natural }", None).unwrap();
+    let yaml = serde_yaml::from_str("a:\n  - 1\n  - 2\n").unwrap();
+    assert_eq!(
+        from_yaml(&yaml, &ty).unwrap().to_expr().to_string(),
+        "{ a = [1, 2] }"
+    );
+}
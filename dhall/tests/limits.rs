@@ -0,0 +1,113 @@
+use dhall::de::{from_str, from_str_with_limits, EvalLimits, Type};
+use dhall::error::{Error, ResourceLimit};
+
+fn limit_hit<T: dhall::de::Deserialize>(
+    s: &str,
+    limits: EvalLimits,
+) -> Option<ResourceLimit> {
+    match from_str_with_limits::<T>(s, None, &limits) {
+        Err(Error::ResourceLimit(limit)) => Some(limit),
+        _ => None,
+    }
+}
+
+#[test]
+fn steps() {
+    let limits = EvalLimits {
+        max_steps: Some(1000),
+        ..EvalLimits::default()
+    };
+    assert_eq!(
+        limit_hit::<u64>(
+            "Natural/fold 1000000000 Natural (λ(x : Natural) → x + 1) 0",
+            limits
+        ),
+        Some(ResourceLimit::Steps)
+    );
+    // Small computations still go through
+    assert_eq!(
+        from_str_with_limits::<u64>(
+            "Natural/fold 10 Natural (λ(x : Natural) → x + 1) 0",
+            None,
+            &limits
+        )
+        .unwrap(),
+        10
+    );
+}
+
+#[test]
+fn list_length() {
+    let limits = EvalLimits {
+        max_list_length: Some(100),
+        ..EvalLimits::default()
+    };
+    let list = "List/build Natural (λ(list : Type) → λ(cons : Natural → list → list) → λ(nil : list) → Natural/fold 200 list (cons 0) nil)";
+    assert_eq!(
+        limit_hit::<Vec<u64>>(list, limits),
+        Some(ResourceLimit::ListLength)
+    );
+    assert_eq!(from_str::<Vec<u64>>(list, None).unwrap().len(), 200);
+}
+
+#[test]
+fn text_length() {
+    let limits = EvalLimits {
+        max_text_length: Some(100),
+        ..EvalLimits::default()
+    };
+    assert_eq!(
+        limit_hit::<String>(
+            "Natural/fold 1000 Text (λ(t : Text) → t ++ \"a\") \"\"",
+            limits
+        ),
+        Some(ResourceLimit::TextLength)
+    );
+    let limits = EvalLimits {
+        max_text_length: Some(10),
+        ..EvalLimits::default()
+    };
+    // The literal fits, but not once quoted
+    assert_eq!(
+        limit_hit::<String>("Text/show \"aaaaaaaaaa\"", limits),
+        Some(ResourceLimit::TextLength)
+    );
+    // Neither half is too long on its own
+    assert_eq!(
+        limit_hit::<String>(
+            "λ(x : Text) → \"aaaaaa${x}\" ++ \"bbbbbb\"",
+            limits
+        ),
+        Some(ResourceLimit::TextLength)
+    );
+}
+
+#[test]
+fn depth() {
+    let limits = EvalLimits {
+        max_depth: Some(500),
+        ..EvalLimits::default()
+    };
+    assert_eq!(
+        limit_hit::<u64>(
+            "Natural/fold 100000 Natural (λ(x : Natural) → x + 1) 0",
+            limits
+        ),
+        Some(ResourceLimit::Depth)
+    );
+}
+
+#[test]
+fn values_shared_with_the_caller_stay_usable() {
+    let limits = EvalLimits {
+        max_steps: Some(1000),
+        ..EvalLimits::default()
+    };
+    let ty: Type = from_str("Natural", None).unwrap();
+    let fold = "Natural/fold 1000000000 Natural (λ(x : Natural) → x + 1) 0";
+    match from_str_with_limits::<u64>(fold, Some(&ty), &limits) {
+        Err(Error::ResourceLimit(ResourceLimit::Steps)) => {}
+        _ => panic!("expected the step limit to be hit"),
+    }
+    assert_eq!(from_str::<u64>("1 + 1", Some(&ty)).unwrap(), 2);
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "sync")]
+use dhall::de::{Deserialize, StaticType, Type, Value};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn values_are_send_sync() {
+    assert_send_sync::<Value>();
+    assert_send_sync::<Type>();
+}
+
+#[test]
+fn move_values_between_threads() {
+    let ty = <Vec<u64>>::static_type();
+    let v = Value::from_str("List/reverse Natural [1, 2, 3]", Some(&ty))
+        .unwrap();
+    let (v, ty) = std::thread::spawn(move || {
+        // Force normalization on another thread
+        assert_eq!(<Vec<u64>>::from_dhall(&v).unwrap(), vec![3, 2, 1]);
+        (v, ty)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(ty, <Vec<u64>>::static_type());
+    assert_eq!(<Vec<u64>>::from_dhall(&v).unwrap(), vec![3, 2, 1]);
+}
@@ -0,0 +1,219 @@
+//! The `dhall` command-line tool.
+//!
+//! Every subcommand reads an expression from stdin, or from the file given
+//! with `--file`, and prints its result to stdout. Errors are printed to
+//! stderr and make the tool exit with a nonzero status.
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+
+use dhall::de::{Type, Value};
+use dhall::error::Result;
+use dhall::json;
+use dhall::pipeline::Parsed;
+use dhall_syntax::cst::{Cst, Trivia};
+use dhall_syntax::{CharacterSet, FormatOptions, SubExpr};
+
+fn main() {
+    let subcommands = vec![
+        ("normalize", "Prints the normal form of the input"),
+        ("type", "Prints the type of the input"),
+        ("resolve", "Replaces the imports of the input"),
+        ("format", "Prints the input in the standard format"),
+        ("freeze", "Adds semantic hashes to the imports of the input"),
+        ("encode", "Encodes the input to CBOR"),
+        ("decode", "Decodes the input from CBOR and prints it"),
+        ("hash", "Prints the semantic hash of the input"),
+        ("to-json", "Converts the value of the input to JSON"),
+        ("to-yaml", "Converts the value of the input to YAML"),
+        ("from-json", "Converts JSON to a Dhall value of type TYPE"),
+        ("from-yaml", "Converts YAML to a Dhall value of type TYPE"),
+    ];
+    let matches = App::new("dhall")
+        .about("Evaluates and manipulates Dhall expressions")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(
+            Arg::with_name("file")
+                .long("file")
+                .value_name("FILE")
+                .global(true)
+                .help("Reads the input from FILE instead of stdin"),
+        )
+        .arg(
+            Arg::with_name("ascii")
+                .long("ascii")
+                .global(true)
+                .help("Prints ASCII instead of Unicode symbols"),
+        )
+        .subcommands(subcommands.into_iter().map(|(name, about)| {
+            let subcommand = SubCommand::with_name(name).about(about);
+            if name.starts_with("from-") {
+                subcommand.arg(
+                    Arg::with_name("type")
+                        .value_name("TYPE")
+                        .required(true)
+                        .help("A Dhall expression for the expected type"),
+                )
+            } else if name.starts_with("to-") {
+                subcommand.arg(
+                    Arg::with_name("preserve-null")
+                        .long("preserve-null")
+                        .help("Keeps the fields of objects that are null"),
+                )
+            } else {
+                subcommand
+            }
+        }))
+        .get_matches();
+
+    let (command, args) = matches.subcommand();
+    if let Err(err) = run(command, args.unwrap()) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn run(
+    command: &str,
+    args: &ArgMatches,
+) -> std::result::Result<(), Box<dyn Error>> {
+    let file = args.value_of_os("file").map(Path::new);
+    let options = FormatOptions {
+        charset: if args.is_present("ascii") {
+            CharacterSet::Ascii
+        } else {
+            CharacterSet::Unicode
+        },
+        ..FormatOptions::default()
+    };
+    let json_options = json::JsonOptions {
+        preserve_null: args.is_present("preserve-null"),
+    };
+    match command {
+        "normalize" => {
+            let expr = parse(file)?.resolve()?.typecheck()?.normalize();
+            print(&expr.to_expr(), &options)
+        }
+        "type" => {
+            let expr = parse(file)?.resolve()?.typecheck()?;
+            print(&expr.get_type()?.to_expr(), &options)
+        }
+        "resolve" => print(&parse(file)?.resolve()?.to_expr(), &options),
+        "format" => println!("{}", format(file, &options)?),
+        "freeze" => print(&parse(file)?.freeze()?.to_expr(), &options),
+        "encode" => io::stdout().write_all(&parse(file)?.encode()?)?,
+        "decode" => print(&decode(file)?.to_expr(), &options),
+        "hash" => {
+            let expr = parse(file)?.resolve()?.typecheck()?.normalize();
+            println!("{}", expr.semantic_hash()?)
+        }
+        "to-json" => {
+            let json = json::to_json_with(&value(file)?, &json_options)?;
+            println!("{}", serde_json::to_string_pretty(&json)?)
+        }
+        "to-yaml" => {
+            let yaml = json::to_yaml_with(&value(file)?, &json_options)?;
+            println!("{}", serde_yaml::to_string(&yaml)?)
+        }
+        "from-json" | "from-yaml" => {
+            let ty: Type =
+                dhall::de::from_str(args.value_of("type").unwrap(), None)?;
+            let input = read_input(file)?;
+            let value = if command == "from-json" {
+                json::from_json(&serde_json::from_str(&input)?, &ty)?
+            } else {
+                json::from_yaml(&serde_yaml::from_str(&input)?, &ty)?
+            };
+            print(&value.to_expr(), &options)
+        }
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+fn print<S: Clone, A: Display + Clone>(
+    expr: &SubExpr<S, A>,
+    options: &FormatOptions,
+) {
+    println!("{}", expr.format(options))
+}
+
+/// Reads an expression from `file`, or from stdin if there is none. Imports
+/// are relative to the file, or to the current directory.
+fn parse(file: Option<&Path>) -> Result<Parsed> {
+    match file {
+        Some(f) => Parsed::parse_file(f),
+        None => Parsed::parse_str(&read_input(None)?),
+    }
+}
+
+/// Formats the input. Like `dhall format`, this keeps the comments at the
+/// start of the input; comments anywhere else would be lost, so they are
+/// refused.
+fn format(
+    file: Option<&Path>,
+    options: &FormatOptions,
+) -> std::result::Result<String, Box<dyn Error>> {
+    let mut cst = Cst::parse(&read_input(file)?)?;
+    let header: String = match cst.root.first_token_mut() {
+        Some(t) => std::mem::replace(&mut t.leading_trivia, Vec::new())
+            .iter()
+            .map(Trivia::as_str)
+            .collect(),
+        None => String::new(),
+    };
+    let has_comments = cst.root.tokens().iter().any(|t| {
+        t.leading_trivia.iter().any(|trivia| match trivia {
+            Trivia::Whitespace(_) => false,
+            Trivia::LineComment(_) | Trivia::BlockComment(_) => true,
+        })
+    });
+    if has_comments {
+        return Err("Cannot format an expression with comments inside it: \
+                    they would be lost"
+            .into());
+    }
+    let expr = cst.to_expr()?.format(options);
+    let header = header.trim();
+    if header.is_empty() {
+        Ok(expr)
+    } else {
+        Ok(format!("{}\n{}", header, expr))
+    }
+}
+
+/// Like [parse], but also typechecks the expression.
+fn value(file: Option<&Path>) -> Result<Value> {
+    match file {
+        Some(f) => Value::from_file(f, None),
+        None => Value::from_str(&read_input(None)?, None),
+    }
+}
+
+/// Like [parse], for CBOR-encoded expressions.
+fn decode(file: Option<&Path>) -> Result<Parsed> {
+    match file {
+        Some(f) => Parsed::parse_binary_file(f),
+        None => {
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data)?;
+            Parsed::parse_binary(&data)
+        }
+    }
+}
+
+/// Reads the contents of `file`, or of stdin if there is none.
+fn read_input(file: Option<&Path>) -> io::Result<String> {
+    let mut s = String::new();
+    match file {
+        Some(f) => File::open(f)?.read_to_string(&mut s)?,
+        None => io::stdin().read_to_string(&mut s)?,
+    };
+    Ok(s)
+}
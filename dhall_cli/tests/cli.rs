@@ -0,0 +1,101 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+/// The `dhall` binary, which cargo builds next to the test executables.
+fn dhall_bin() -> PathBuf {
+    let mut path = std::env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.join("dhall")
+}
+
+fn run(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(dhall_bin())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(args: &[&str], input: &str) -> String {
+    let output = run(args, input);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn normalize() {
+    assert_eq!(stdout(&["normalize"], "let x = 1 in x + 2"), "3\n");
+}
+
+#[test]
+fn type_() {
+    assert_eq!(stdout(&["type"], "[1, 2]"), "List Natural\n");
+}
+
+#[test]
+fn format() {
+    assert_eq!(
+        stdout(&["format"], "-- the answer\n{x=42}"),
+        "-- the answer\n{ x = 42 }\n"
+    );
+    let output = run(&["format"], "{ x = 42 -- the answer\n}");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Cannot format "), "{}", stderr);
+}
+
+#[test]
+fn errors() {
+    let output = run(&["normalize"], "1 + True");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Type error: "), "{}", stderr);
+    assert!(output.stdout.is_empty());
+
+    let output = run(&["resolve"], "https://example.com/x.dhall");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Cannot import "), "{}", stderr);
+}
+
+#[test]
+fn freeze() {
+    let dir = std::env::temp_dir()
+        .join(format!("dhall-cli-freeze-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("b"), "1 + 1").unwrap();
+    fs::write(dir.join("a.dhall"), "./b + 1").unwrap();
+    let a = dir.join("a.dhall");
+    let frozen = stdout(&["freeze", "--file", a.to_str().unwrap()], "");
+    let hash = stdout(&["hash"], "2");
+    assert_eq!(frozen, format!("./b {} + 1\n", hash.trim_end()));
+    // The frozen expression still evaluates the same
+    fs::write(&a, &frozen).unwrap();
+    let normalized = stdout(&["normalize", "--file", a.to_str().unwrap()], "");
+    assert_eq!(normalized, "3\n");
+    // Changing what a frozen import points to is an error
+    fs::write(dir.join("b"), "1 + 2").unwrap();
+    let output = run(&["normalize", "--file", a.to_str().unwrap()], "");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Hash mismatch for ./b "), "{}", stderr);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -60,7 +60,8 @@ where
             quote! { dhall_syntax::ExprF::BinOp(#o, #a, #b) }
         }
         NaturalLit(n) => {
-            quote! { dhall_syntax::ExprF::NaturalLit(#n) }
+            let n = n.to_string();
+            quote! { dhall_syntax::ExprF::NaturalLit(#n.parse().unwrap()) }
         }
         BoolLit(b) => {
             quote! { dhall_syntax::ExprF::BoolLit(#b) }
@@ -1,11 +1,9 @@
-use std::rc::Rc;
-
 use crate::map::DupTreeMap;
+use crate::stack::ensure_sufficient_stack;
+use crate::sync::Rc;
 use crate::visitor;
 use crate::*;
 
-pub type Integer = isize;
-pub type Natural = usize;
 pub type Double = NaiveDouble;
 
 /// An empty type
@@ -43,8 +41,9 @@ impl From<NaiveDouble> for f64 {
     }
 }
 
-/// Constants for a pure type system
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Constants for a pure type system.
+/// Ordered by universe level: `Type < Kind < Sort`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Const {
     Type,
     Kind,
@@ -102,7 +101,7 @@ pub enum BinOp {
 }
 
 /// Built-ins
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Builtin {
     Bool,
     Natural,
@@ -144,7 +143,7 @@ pub struct SubExpr<Note, Embed>(Rc<(Expr<Note, Embed>, Option<Note>)>);
 
 impl<Note, Embed: PartialEq> std::cmp::PartialEq for SubExpr<Note, Embed> {
     fn eq(&self, other: &Self) -> bool {
-        self.0.as_ref().0 == other.0.as_ref().0
+        ensure_sufficient_stack(|| self.0.as_ref().0 == other.0.as_ref().0)
     }
 }
 
@@ -330,6 +329,10 @@ impl<N, E> SubExpr<N, E> {
         &self.0.as_ref().0
     }
 
+    pub(crate) fn note(&self) -> Option<&N> {
+        (self.0).1.as_ref()
+    }
+
     pub fn new(x: Expr<N, E>, n: N) -> Self {
         SubExpr(Rc::new((x, Some(n))))
     }
@@ -407,6 +410,18 @@ impl<N, E> Clone for SubExpr<N, E> {
     }
 }
 
+impl<N, E> Drop for SubExpr<N, E> {
+    fn drop(&mut self) {
+        // The default drop glue recurses into subexpressions, which would
+        // overflow the stack on deep expressions. If we own the last
+        // reference, drop the contents ourselves with enough stack.
+        if let Some((e, _)) = Rc::get_mut(&mut self.0) {
+            let e = std::mem::replace(e, ExprF::Const(Const::Type));
+            ensure_sufficient_stack(move || drop(e));
+        }
+    }
+}
+
 // Should probably rename this
 pub fn rc<E>(x: Expr<X, E>) -> SubExpr<X, E> {
     SubExpr::from_expr_no_note(x)
@@ -1,34 +1,168 @@
-use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::sync::{Rc, Weak};
+
+/// The name of a label, along with its hash computed once and for all.
+struct Symbol {
+    hash: u64,
+    name: Box<str>,
+}
+
+/// Labels created on the same thread share one allocation per name. Entries
+/// are weak, so a name is freed once no label uses it anymore.
+struct SymbolTable {
+    symbols: HashMap<u64, Vec<Weak<Symbol>>>,
+    /// Dead entries get pruned when the table grows past this size.
+    prune_at: usize,
+}
+
+impl SymbolTable {
+    fn new() -> Self {
+        SymbolTable {
+            symbols: HashMap::new(),
+            prune_at: 1024,
+        }
+    }
+
+    fn intern(&mut self, name: &str) -> Rc<Symbol> {
+        let hash = hash_name(name);
+        if let Some(candidates) = self.symbols.get(&hash) {
+            let shared = candidates
+                .iter()
+                .filter_map(Weak::upgrade)
+                .find(|sym| &*sym.name == name);
+            if let Some(sym) = shared {
+                return sym;
+            }
+        }
+        if self.symbols.len() >= self.prune_at {
+            self.symbols.retain(|_, ws| {
+                ws.retain(|w| w.upgrade().is_some());
+                !ws.is_empty()
+            });
+            self.prune_at = std::cmp::max(1024, 2 * self.symbols.len());
+        }
+        let sym = Rc::new(Symbol {
+            hash,
+            name: name.into(),
+        });
+        self.symbols
+            .entry(hash)
+            .or_default()
+            .push(Rc::downgrade(&sym));
+        sym
+    }
+}
+
+thread_local! {
+    static SYMBOLS: RefCell<SymbolTable> = RefCell::new(SymbolTable::new());
+}
+
+/// Same on every thread, so that labels from different threads can be
+/// compared by hash.
+fn hash_name(name: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn intern(name: &str) -> Rc<Symbol> {
+    SYMBOLS.with(|symbols| symbols.borrow_mut().intern(name))
+}
 
 // The type for labels throughout the AST
-// It owns the data because otherwise lifetimes would make recursive imports impossible
-#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct Label(Rc<str>);
+// Labels are interned per thread and carry their hash, so equality is a
+// pointer comparison for labels from the same thread, and hashing never
+// looks at the name.
+#[derive(Clone)]
+pub struct Label(Rc<Symbol>);
+
+impl PartialEq for Label {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+            || (self.0.hash == other.0.hash && self.0.name == other.0.name)
+    }
+}
+
+impl Eq for Label {}
+
+impl Hash for Label {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash.hash(state)
+    }
+}
+
+// Labels are ordered alphabetically, e.g. when sorting record fields
+impl PartialOrd for Label {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Label {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        if self == other {
+            std::cmp::Ordering::Equal
+        } else {
+            self.as_ref().cmp(other.as_ref())
+        }
+    }
+}
+
+impl std::fmt::Debug for Label {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Label").field(&self.as_ref()).finish()
+    }
+}
 
 impl From<String> for Label {
     fn from(s: String) -> Self {
-        let s: &str = &s;
-        Label(s.into())
+        Label(intern(&s))
     }
 }
 
 impl<'a> From<&'a str> for Label {
     fn from(s: &'a str) -> Self {
-        Label(Rc::from(s))
+        Label(intern(s))
     }
 }
 
 impl From<&Label> for String {
     fn from(x: &Label) -> String {
-        x.0.as_ref().to_owned()
+        x.as_ref().to_owned()
     }
 }
 
 impl Label {
     pub fn from_str(s: &str) -> Label {
-        Label(s.into())
+        Label(intern(s))
     }
     pub fn as_ref(&self) -> &str {
-        self.0.as_ref()
+        &self.0.name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unused_labels_are_freed() {
+        let weak = Rc::downgrade(&Label::from("some_unique_label").0);
+        assert!(weak.upgrade().is_none());
+        let x = Label::from("x");
+        assert!(Rc::ptr_eq(&x.0, &Label::from(String::from("x")).0));
+    }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn labels_from_other_threads_are_equal() {
+        let other = std::thread::spawn(|| Label::from("x")).join().unwrap();
+        let x = Label::from("x");
+        assert!(!Rc::ptr_eq(&x.0, &other.0));
+        assert_eq!(x, other);
     }
 }
@@ -4,6 +4,8 @@ mod import;
 pub use import::*;
 mod label;
 pub use label::*;
+mod number;
+pub use number::*;
 mod text;
 pub use text::*;
 pub mod context;
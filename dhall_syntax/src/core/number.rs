@@ -0,0 +1,276 @@
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+use num_bigint::{BigInt, BigUint, ParseBigIntError, Sign};
+use num_traits::{ToPrimitive, Zero};
+
+/// An unbounded natural number. Numbers that fit in a `u64` are stored inline;
+/// only bigger ones allocate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Natural(NaturalRepr);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum NaturalRepr {
+    Small(u64),
+    /// Always bigger than `u64::MAX`, so that each number has a single
+    /// representation
+    Big(BigUint),
+}
+
+/// An unbounded integer. Numbers that fit in an `i64` are stored inline;
+/// only bigger ones allocate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Integer(IntegerRepr);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum IntegerRepr {
+    Small(i64),
+    /// Always outside the range of `i64`
+    Big(BigInt),
+}
+
+impl Natural {
+    pub fn is_zero(&self) -> bool {
+        self.0 == NaturalRepr::Small(0)
+    }
+    pub fn is_one(&self) -> bool {
+        self.0 == NaturalRepr::Small(1)
+    }
+    pub fn is_even(&self) -> bool {
+        match &self.0 {
+            NaturalRepr::Small(n) => n % 2 == 0,
+            NaturalRepr::Big(n) => (n % 2u32).is_zero(),
+        }
+    }
+    pub fn to_biguint(&self) -> BigUint {
+        match &self.0 {
+            NaturalRepr::Small(n) => BigUint::from(*n),
+            NaturalRepr::Big(n) => n.clone(),
+        }
+    }
+}
+
+impl Integer {
+    pub fn is_negative(&self) -> bool {
+        match &self.0 {
+            IntegerRepr::Small(n) => *n < 0,
+            IntegerRepr::Big(n) => n.sign() == Sign::Minus,
+        }
+    }
+    pub fn to_bigint(&self) -> BigInt {
+        match &self.0 {
+            IntegerRepr::Small(n) => BigInt::from(*n),
+            IntegerRepr::Big(n) => n.clone(),
+        }
+    }
+}
+
+impl From<u64> for Natural {
+    fn from(n: u64) -> Self {
+        Natural(NaturalRepr::Small(n))
+    }
+}
+
+impl From<usize> for Natural {
+    fn from(n: usize) -> Self {
+        Natural::from(n as u64)
+    }
+}
+
+impl From<BigUint> for Natural {
+    fn from(n: BigUint) -> Self {
+        match n.to_u64() {
+            Some(n) => Natural::from(n),
+            None => Natural(NaturalRepr::Big(n)),
+        }
+    }
+}
+
+impl From<i64> for Integer {
+    fn from(n: i64) -> Self {
+        Integer(IntegerRepr::Small(n))
+    }
+}
+
+impl From<BigInt> for Integer {
+    fn from(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(n) => Integer::from(n),
+            None => Integer(IntegerRepr::Big(n)),
+        }
+    }
+}
+
+impl From<Natural> for Integer {
+    fn from(n: Natural) -> Self {
+        match n.0 {
+            NaturalRepr::Small(n) if n <= i64::max_value() as u64 => {
+                Integer::from(n as i64)
+            }
+            _ => Integer::from(BigInt::from(n.to_biguint())),
+        }
+    }
+}
+
+impl<'a> Add for &'a Natural {
+    type Output = Natural;
+    fn add(self, other: &'a Natural) -> Natural {
+        if let (NaturalRepr::Small(x), NaturalRepr::Small(y)) =
+            (&self.0, &other.0)
+        {
+            if let Some(n) = x.checked_add(*y) {
+                return Natural::from(n);
+            }
+        }
+        Natural::from(self.to_biguint() + other.to_biguint())
+    }
+}
+
+impl<'a> Mul for &'a Natural {
+    type Output = Natural;
+    fn mul(self, other: &'a Natural) -> Natural {
+        if let (NaturalRepr::Small(x), NaturalRepr::Small(y)) =
+            (&self.0, &other.0)
+        {
+            if let Some(n) = x.checked_mul(*y) {
+                return Natural::from(n);
+            }
+        }
+        Natural::from(self.to_biguint() * other.to_biguint())
+    }
+}
+
+impl<'a> Add<u64> for &'a Natural {
+    type Output = Natural;
+    fn add(self, other: u64) -> Natural {
+        self + &Natural::from(other)
+    }
+}
+
+/// Panics if the result would be negative, like subtraction on `u64`.
+impl<'a> Sub<u64> for &'a Natural {
+    type Output = Natural;
+    fn sub(self, other: u64) -> Natural {
+        match &self.0 {
+            NaturalRepr::Small(n) => Natural::from(n - other),
+            NaturalRepr::Big(n) => Natural::from(n - other),
+        }
+    }
+}
+
+impl ToPrimitive for Natural {
+    fn to_i64(&self) -> Option<i64> {
+        self.to_u64()?.to_i64()
+    }
+    fn to_u64(&self) -> Option<u64> {
+        match &self.0 {
+            NaturalRepr::Small(n) => Some(*n),
+            NaturalRepr::Big(_) => None,
+        }
+    }
+    fn to_u128(&self) -> Option<u128> {
+        match &self.0 {
+            NaturalRepr::Small(n) => Some(u128::from(*n)),
+            NaturalRepr::Big(n) => n.to_u128(),
+        }
+    }
+    fn to_i128(&self) -> Option<i128> {
+        self.to_u128()?.to_i128()
+    }
+    fn to_f64(&self) -> Option<f64> {
+        match &self.0 {
+            NaturalRepr::Small(n) => n.to_f64(),
+            NaturalRepr::Big(n) => n.to_f64(),
+        }
+    }
+}
+
+impl ToPrimitive for Integer {
+    fn to_i64(&self) -> Option<i64> {
+        match &self.0 {
+            IntegerRepr::Small(n) => Some(*n),
+            IntegerRepr::Big(_) => None,
+        }
+    }
+    fn to_u64(&self) -> Option<u64> {
+        match &self.0 {
+            IntegerRepr::Small(n) => n.to_u64(),
+            IntegerRepr::Big(n) => n.to_u64(),
+        }
+    }
+    fn to_i128(&self) -> Option<i128> {
+        match &self.0 {
+            IntegerRepr::Small(n) => Some(i128::from(*n)),
+            IntegerRepr::Big(n) => n.to_i128(),
+        }
+    }
+    fn to_u128(&self) -> Option<u128> {
+        self.to_i128()?.to_u128()
+    }
+    fn to_f64(&self) -> Option<f64> {
+        match &self.0 {
+            IntegerRepr::Small(n) => n.to_f64(),
+            IntegerRepr::Big(n) => n.to_f64(),
+        }
+    }
+}
+
+impl FromStr for Natural {
+    type Err = ParseBigIntError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<u64>() {
+            Ok(n) => Ok(Natural::from(n)),
+            Err(_) => Ok(Natural::from(s.parse::<BigUint>()?)),
+        }
+    }
+}
+
+impl FromStr for Integer {
+    type Err = ParseBigIntError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i64>() {
+            Ok(n) => Ok(Integer::from(n)),
+            Err(_) => Ok(Integer::from(s.parse::<BigInt>()?)),
+        }
+    }
+}
+
+impl fmt::Display for Natural {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            NaturalRepr::Small(n) => n.fmt(f),
+            NaturalRepr::Big(n) => n.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            IntegerRepr::Small(n) => n.fmt(f),
+            IntegerRepr::Big(n) => n.fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_and_big() {
+        let max = Natural::from(u64::max_value());
+        assert_eq!(max.to_u64(), Some(u64::max_value()));
+        let big = &max + 1;
+        assert_eq!(big.to_u64(), None);
+        assert_eq!(big.to_string(), "18446744073709551616");
+        assert_eq!(&big - 1, max);
+        assert_eq!(big, "18446744073709551616".parse().unwrap());
+        assert_eq!(&max * &Natural::from(1u64), max);
+        assert_eq!(Integer::from(big).to_string(), "18446744073709551616");
+        let min = Integer::from(i64::min_value());
+        assert_eq!(min, Integer::from(min.to_bigint()));
+        assert!(min.is_negative());
+    }
+}
@@ -123,3 +123,56 @@ impl<SubExpr> FromIterator<InterpolatedTextContents<SubExpr>>
         res
     }
 }
+
+/// Escapes a string so that it can be put between double quotes in a Dhall
+/// text literal. This follows the rules the standard prescribes for
+/// `Text/show`: quotes, backslashes and the usual control characters get
+/// their short escape, `$` becomes `\u0024` so that it can never start an
+/// interpolation, and any other control character is written as `\uXXXX`.
+pub fn escape_text(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '$' => escaped.push_str("\\u0024"),
+            '\u{0008}' => escaped.push_str("\\b"),
+            '\u{000C}' => escaped.push_str("\\f"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{0000}'..='\u{001F}' => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Decodes the contents of a `\u` escape, i.e. either `XXXX` or `{X...}`.
+/// Surrogate code points are not valid Unicode scalar values and are rejected.
+pub fn unescape_unicode(s: &str) -> Option<char> {
+    let hex = if s.starts_with('{') && s.ends_with('}') {
+        &s[1..s.len() - 1]
+    } else if s.len() == 4 {
+        s
+    } else {
+        return None;
+    };
+    let code = u32::from_str_radix(hex, 16).ok()?;
+    std::char::from_u32(code)
+}
+
+#[test]
+fn test_escape_text() {
+    assert_eq!(escape_text("foo"), "foo");
+    assert_eq!(escape_text("\"${x}\\"), "\\\"\\u0024{x}\\\\");
+    assert_eq!(escape_text("a\tb\n"), "a\\tb\\n");
+    assert_eq!(escape_text("\u{0001}\u{001F}"), "\\u0001\\u001f");
+    assert_eq!(escape_text("λ→ü"), "λ→ü");
+    assert_eq!(unescape_unicode("0024"), Some('$'));
+    assert_eq!(unescape_unicode("{1F600}"), Some('\u{1F600}'));
+    assert_eq!(unescape_unicode("D800"), None);
+    assert_eq!(unescape_unicode("{110000}"), None);
+}
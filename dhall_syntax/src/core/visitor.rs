@@ -1,3 +1,4 @@
+use crate::stack::ensure_sufficient_stack;
 use crate::*;
 use std::iter::FromIterator;
 
@@ -98,64 +99,68 @@ where
                 .collect()
         }
 
-        let mut v = self;
-        use crate::ExprF::*;
-        T::visit_resulting_exprf(match input {
-            Var(v) => Var(v.clone()),
-            Lam(l, t, e) => {
-                let t = v.visit_subexpr(t)?;
-                let e = v.visit_subexpr_under_binder(l, e)?;
-                Lam(l.clone(), t, e)
-            }
-            Pi(l, t, e) => {
-                let t = v.visit_subexpr(t)?;
-                let e = v.visit_subexpr_under_binder(l, e)?;
-                Pi(l.clone(), t, e)
-            }
-            Let(l, t, a, e) => {
-                let t = opt(t, &mut |e| v.visit_subexpr(e))?;
-                let a = v.visit_subexpr(a)?;
-                let e = v.visit_subexpr_under_binder(l, e)?;
-                Let(l.clone(), t, a, e)
-            }
-            App(f, a) => App(v.visit_subexpr(f)?, v.visit_subexpr(a)?),
-            Annot(x, t) => Annot(v.visit_subexpr(x)?, v.visit_subexpr(t)?),
-            Const(k) => Const(*k),
-            Builtin(v) => Builtin(*v),
-            BoolLit(b) => BoolLit(*b),
-            NaturalLit(n) => NaturalLit(*n),
-            IntegerLit(n) => IntegerLit(*n),
-            DoubleLit(n) => DoubleLit(*n),
-            TextLit(t) => TextLit(t.traverse_ref(|e| v.visit_subexpr(e))?),
-            BinOp(o, x, y) => {
-                BinOp(*o, v.visit_subexpr(x)?, v.visit_subexpr(y)?)
-            }
-            BoolIf(b, t, f) => BoolIf(
-                v.visit_subexpr(b)?,
-                v.visit_subexpr(t)?,
-                v.visit_subexpr(f)?,
-            ),
-            EmptyListLit(t) => EmptyListLit(v.visit_subexpr(t)?),
-            NEListLit(es) => NEListLit(vec(es, |e| v.visit_subexpr(e))?),
-            OldOptionalLit(x, t) => OldOptionalLit(
-                opt(x, |e| v.visit_subexpr(e))?,
-                v.visit_subexpr(t)?,
-            ),
-            SomeLit(e) => SomeLit(v.visit_subexpr(e)?),
-            RecordType(kts) => RecordType(dupmap(kts, v)?),
-            RecordLit(kvs) => RecordLit(dupmap(kvs, v)?),
-            UnionType(kts) => UnionType(optdupmap(kts, v)?),
-            UnionLit(k, x, kts) => {
-                UnionLit(k.clone(), v.visit_subexpr(x)?, optdupmap(kts, v)?)
-            }
-            Merge(x, y, t) => Merge(
-                v.visit_subexpr(x)?,
-                v.visit_subexpr(y)?,
-                opt(t, |e| v.visit_subexpr(e))?,
-            ),
-            Field(e, l) => Field(v.visit_subexpr(e)?, l.clone()),
-            Projection(e, ls) => Projection(v.visit_subexpr(e)?, ls.clone()),
-            Embed(a) => return v.visit_embed_squash(a),
+        ensure_sufficient_stack(move || {
+            let mut v = self;
+            use crate::ExprF::*;
+            T::visit_resulting_exprf(match input {
+                Var(v) => Var(v.clone()),
+                Lam(l, t, e) => {
+                    let t = v.visit_subexpr(t)?;
+                    let e = v.visit_subexpr_under_binder(l, e)?;
+                    Lam(l.clone(), t, e)
+                }
+                Pi(l, t, e) => {
+                    let t = v.visit_subexpr(t)?;
+                    let e = v.visit_subexpr_under_binder(l, e)?;
+                    Pi(l.clone(), t, e)
+                }
+                Let(l, t, a, e) => {
+                    let t = opt(t, &mut |e| v.visit_subexpr(e))?;
+                    let a = v.visit_subexpr(a)?;
+                    let e = v.visit_subexpr_under_binder(l, e)?;
+                    Let(l.clone(), t, a, e)
+                }
+                App(f, a) => App(v.visit_subexpr(f)?, v.visit_subexpr(a)?),
+                Annot(x, t) => Annot(v.visit_subexpr(x)?, v.visit_subexpr(t)?),
+                Const(k) => Const(*k),
+                Builtin(v) => Builtin(*v),
+                BoolLit(b) => BoolLit(*b),
+                NaturalLit(n) => NaturalLit(n.clone()),
+                IntegerLit(n) => IntegerLit(n.clone()),
+                DoubleLit(n) => DoubleLit(*n),
+                TextLit(t) => TextLit(t.traverse_ref(|e| v.visit_subexpr(e))?),
+                BinOp(o, x, y) => {
+                    BinOp(*o, v.visit_subexpr(x)?, v.visit_subexpr(y)?)
+                }
+                BoolIf(b, t, f) => BoolIf(
+                    v.visit_subexpr(b)?,
+                    v.visit_subexpr(t)?,
+                    v.visit_subexpr(f)?,
+                ),
+                EmptyListLit(t) => EmptyListLit(v.visit_subexpr(t)?),
+                NEListLit(es) => NEListLit(vec(es, |e| v.visit_subexpr(e))?),
+                OldOptionalLit(x, t) => OldOptionalLit(
+                    opt(x, |e| v.visit_subexpr(e))?,
+                    v.visit_subexpr(t)?,
+                ),
+                SomeLit(e) => SomeLit(v.visit_subexpr(e)?),
+                RecordType(kts) => RecordType(dupmap(kts, v)?),
+                RecordLit(kvs) => RecordLit(dupmap(kvs, v)?),
+                UnionType(kts) => UnionType(optdupmap(kts, v)?),
+                UnionLit(k, x, kts) => {
+                    UnionLit(k.clone(), v.visit_subexpr(x)?, optdupmap(kts, v)?)
+                }
+                Merge(x, y, t) => Merge(
+                    v.visit_subexpr(x)?,
+                    v.visit_subexpr(y)?,
+                    opt(t, |e| v.visit_subexpr(e))?,
+                ),
+                Field(e, l) => Field(v.visit_subexpr(e)?, l.clone()),
+                Projection(e, ls) => {
+                    Projection(v.visit_subexpr(e)?, ls.clone())
+                }
+                Embed(a) => return v.visit_embed_squash(a),
+            })
         })
     }
 }
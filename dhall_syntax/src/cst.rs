@@ -0,0 +1,330 @@
+//! A lossless concrete syntax tree.
+//!
+//! Unlike [Expr][crate::Expr], the tree keeps every byte of the source:
+//! whitespace and comments are kept as trivia attached to the token that
+//! follows them, so printing a [Cst] gives back the exact input. This makes it
+//! suitable for tools that rewrite source files, since nodes can be edited in
+//! place without losing hand-written comments.
+//!
+//! ```ignore
+//! let mut cst = Cst::parse("-- the answer\n{ x = 42 }")?;
+//! for n in cst.root.find_all_mut(Rule::natural_literal) {
+//!     n.replace(Node::parse(Rule::natural_literal, "43")?);
+//! }
+//! assert_eq!(cst.to_string(), "-- the answer\n{ x = 43 }");
+//! ```
+use pest::iterators::Pair;
+use pest::Parser;
+use std::fmt::{self, Display};
+
+use dhall_generated_parser::DhallParser;
+pub use dhall_generated_parser::Rule;
+
+use crate::stack::ensure_sufficient_stack;
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trivia {
+    Whitespace(String),
+    /// A `--` comment, without the line ending.
+    LineComment(String),
+    /// A `{- -}` comment, possibly nested.
+    BlockComment(String),
+}
+
+impl Trivia {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Trivia::Whitespace(s)
+            | Trivia::LineComment(s)
+            | Trivia::BlockComment(s) => s,
+        }
+    }
+}
+
+/// A piece of source text that is not trivia, together with the trivia that
+/// precedes it. Trivia at the end of the input is attached to an empty token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub leading_trivia: Vec<Trivia>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    Node(Node),
+    Token(Token),
+}
+
+/// The match of a grammar rule. Only the rules that are not silent in the
+/// grammar get a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub rule: Rule,
+    pub children: Vec<Element>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cst {
+    pub root: Node,
+}
+
+impl Cst {
+    pub fn parse(s: &str) -> ParseResult<Self> {
+        Ok(Cst {
+            root: Node::parse(Rule::final_expression, s)?,
+        })
+    }
+
+    /// Converts the tree, including any edits, into an expression.
+    pub fn to_expr(&self) -> ParseResult<SubExpr<Span, Import>> {
+        parse_expr(&self.to_string())
+    }
+}
+
+impl Node {
+    /// Parses `s` as a whole using the grammar rule `rule`. This can be used
+    /// to build replacement nodes.
+    pub fn parse(rule: Rule, s: &str) -> ParseResult<Self> {
+        let pair = DhallParser::parse(rule, s)
+            .map_err(|e| ParseError::new(e, s))?
+            .next()
+            .unwrap();
+        if pair.as_span().end() != s.len() {
+            let e = pest::error::ErrorVariant::CustomError {
+                message: format!("unexpected input after {:?}", rule),
+            };
+            let pos = pest::Position::new(s, pair.as_span().end()).unwrap();
+            let e = pest::error::Error::new_from_pos(e, pos);
+            return Err(ParseError::new(e, s));
+        }
+        let mut pending = Vec::new();
+        let mut node = build(pair, &mut pending, false);
+        if !pending.is_empty() {
+            node.children.push(Element::Token(Token {
+                leading_trivia: pending,
+                text: String::new(),
+            }));
+        }
+        Ok(node)
+    }
+
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = Vec::new();
+        self.visit(&mut |e| {
+            if let Element::Token(t) = e {
+                tokens.push(t)
+            }
+        });
+        tokens
+    }
+
+    pub fn first_token_mut(&mut self) -> Option<&mut Token> {
+        self.children.iter_mut().find_map(|e| match e {
+            Element::Token(t) => Some(t),
+            Element::Node(n) => n.first_token_mut(),
+        })
+    }
+
+    /// The descendants of this node (including itself) that match `rule`,
+    /// outermost first. Matches nested in other matches are not included.
+    pub fn find_all_mut(&mut self, rule: Rule) -> Vec<&mut Node> {
+        if self.rule == rule {
+            return vec![self];
+        }
+        self.children
+            .iter_mut()
+            .flat_map(|e| match e {
+                Element::Node(n) => n.find_all_mut(rule),
+                Element::Token(_) => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Replaces this node with `new`, keeping the comments that preceded it.
+    pub fn replace(&mut self, mut new: Node) {
+        let trivia = match self.first_token_mut() {
+            Some(t) => std::mem::replace(&mut t.leading_trivia, Vec::new()),
+            None => Vec::new(),
+        };
+        if let Some(t) = new.first_token_mut() {
+            let mut new_trivia = trivia;
+            new_trivia.append(&mut t.leading_trivia);
+            t.leading_trivia = new_trivia;
+        }
+        *self = new;
+    }
+
+    fn visit<'a>(&'a self, f: &mut impl FnMut(&'a Element)) {
+        for e in &self.children {
+            f(e);
+            if let Element::Node(n) = e {
+                ensure_sufficient_stack(|| n.visit(f));
+            }
+        }
+    }
+}
+
+/// Builds the node for `pair`. Trivia that has not been attached to a token
+/// yet is accumulated in `pending`.
+fn build(pair: Pair<Rule>, pending: &mut Vec<Trivia>, in_text: bool) -> Node {
+    ensure_sufficient_stack(|| {
+        let rule = pair.as_rule();
+        // Whitespace inside text literals is part of the text
+        let in_text = match rule {
+            Rule::double_quote_literal | Rule::single_quote_literal => true,
+            Rule::interpolation => false,
+            _ => in_text,
+        };
+        let span = pair.as_span();
+        let input = span.as_str();
+        let offset = span.start();
+        let mut children = Vec::new();
+        let mut pos = 0;
+        let mut inner = pair.into_inner().peekable();
+        if inner.peek().is_none() {
+            push_token(&mut children, pending, input);
+            return Node { rule, children };
+        }
+        for child in inner {
+            let child_span = child.as_span();
+            let start = child_span.start() - offset;
+            push_gap(&mut children, pending, &input[pos..start], in_text);
+            children.push(Element::Node(build(child, pending, in_text)));
+            pos = child_span.end() - offset;
+        }
+        push_gap(&mut children, pending, &input[pos..], in_text);
+        Node { rule, children }
+    })
+}
+
+fn push_token(children: &mut Vec<Element>, pending: &mut Vec<Trivia>, s: &str) {
+    if s.is_empty() {
+        return;
+    }
+    children.push(Element::Token(Token {
+        leading_trivia: std::mem::replace(pending, Vec::new()),
+        text: s.to_owned(),
+    }));
+}
+
+/// Splits the text between two child nodes into trivia and tokens.
+fn push_gap(
+    children: &mut Vec<Element>,
+    pending: &mut Vec<Trivia>,
+    mut s: &str,
+    in_text: bool,
+) {
+    if in_text {
+        push_token(children, pending, s);
+        return;
+    }
+    while !s.is_empty() {
+        let len = if s.starts_with("--") {
+            let len = s.find(|c| c == '\n' || c == '\r').unwrap_or(s.len());
+            pending.push(Trivia::LineComment(s[..len].to_owned()));
+            len
+        } else if s.starts_with("{-") {
+            let len = block_comment_len(s);
+            pending.push(Trivia::BlockComment(s[..len].to_owned()));
+            len
+        } else if s.starts_with(is_whitespace) {
+            let len = s.find(|c| !is_whitespace(c)).unwrap_or(s.len());
+            pending.push(Trivia::Whitespace(s[..len].to_owned()));
+            len
+        } else {
+            let len = (1..s.len())
+                .filter(|&i| s.is_char_boundary(i))
+                .find(|&i| {
+                    let rest = &s[i..];
+                    rest.starts_with(is_whitespace)
+                        || rest.starts_with("--")
+                        || rest.starts_with("{-")
+                })
+                .unwrap_or(s.len());
+            push_token(children, pending, &s[..len]);
+            len
+        };
+        s = &s[len..];
+    }
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\n' || c == '\r'
+}
+
+fn block_comment_len(s: &str) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        let rest = &s[i..];
+        if rest.starts_with("{-") {
+            depth += 1;
+            i += 2;
+        } else if rest.starts_with("-}") {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    s.len()
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for t in &self.leading_trivia {
+            t.fmt(f)?;
+        }
+        f.write_str(&self.text)
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for t in self.tokens() {
+            t.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Cst {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.root.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "{- A config -}\nlet x = 1 -- one\n\nin  { y = \
+                          x,\n      {- nested {- comment -} -} z = \"a  b\" \
+                          ++ ''\n  c ${ x }\n  ''\n    }\n-- end\n";
+
+    #[test]
+    fn prints_back_the_source() {
+        let cst = Cst::parse(SOURCE).unwrap();
+        assert_eq!(cst.to_string(), SOURCE);
+        assert_eq!(cst.to_expr().unwrap(), parse_expr(SOURCE).unwrap());
+    }
+
+    #[test]
+    fn edits_keep_comments() {
+        let mut cst = Cst::parse("{- x -} 1 + {- y -} 2").unwrap();
+        for n in cst.root.find_all_mut(Rule::natural_literal) {
+            n.replace(Node::parse(Rule::natural_literal, "3").unwrap());
+        }
+        assert_eq!(cst.to_string(), "{- x -} 3 + {- y -} 3");
+    }
+}
@@ -0,0 +1,681 @@
+//! Width-aware pretty-printing of expressions, following the layout of
+//! `dhall format`.
+//!
+//! Expressions are first turned into a document in the style of Wadler's
+//! "A prettier printer", and then laid out to fit the requested width. We use
+//! our own small document type rather than the `pretty` crate, because Dhall's
+//! layouts need text that differs between the flat and broken versions of a
+//! group, e.g. for leading commas.
+use crate::printer::{NonReservedLabel, PhasedExpr, PrintPhase};
+use crate::stack::ensure_sufficient_stack;
+use crate::*;
+use itertools::Itertools;
+use std::fmt::Display;
+
+/// Options for [SubExpr::format].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The number of columns the output should fit in, where possible.
+    pub width: usize,
+    /// Whether to use Unicode or ASCII for keywords and operators.
+    pub charset: CharacterSet,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            width: 80,
+            charset: CharacterSet::Unicode,
+        }
+    }
+}
+
+impl<S: Clone, A: Display + Clone> SubExpr<S, A> {
+    /// Prints the expression the way `dhall format` would, breaking lines to
+    /// fit within `options.width` when possible.
+    pub fn format(&self, options: &FormatOptions) -> String {
+        let doc = expr_doc(self.as_ref(), PrintPhase::Base, options.charset);
+        render(&doc, options.width)
+    }
+}
+
+enum Doc {
+    Text(String),
+    /// A line break, or the given text if the enclosing group is flat.
+    Line(&'static str),
+    /// A line break that prevents the enclosing groups from being flat.
+    HardLine,
+    /// The first document if the enclosing group is flat, else the second.
+    Alt(Box<Doc>, Box<Doc>),
+    Concat(Vec<Doc>),
+    /// Increases the indentation of the line breaks in the document.
+    Nest(usize, Box<Doc>),
+    /// Sets the indentation of the line breaks in the document to the
+    /// current column.
+    Align(Box<Doc>),
+    /// Lays out the document on a single line if it fits, and breaks all its
+    /// lines otherwise.
+    Group(Box<Doc>),
+}
+
+fn text(s: impl Into<String>) -> Doc {
+    Doc::Text(s.into())
+}
+
+/// A line break, or a space if flat.
+fn line() -> Doc {
+    Doc::Line(" ")
+}
+
+/// A line break, or nothing if flat.
+fn softline() -> Doc {
+    Doc::Line("")
+}
+
+fn alt(flat: Doc, broken: Doc) -> Doc {
+    Doc::Alt(Box::new(flat), Box::new(broken))
+}
+
+fn cat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+fn nest(i: usize, doc: Doc) -> Doc {
+    Doc::Nest(i, Box::new(doc))
+}
+
+fn align(doc: Doc) -> Doc {
+    Doc::Align(Box::new(doc))
+}
+
+fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+fn text_width(s: &str) -> isize {
+    s.chars().count() as isize
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+struct Writer {
+    out: String,
+    col: usize,
+    /// Indentation that has yet to be written. It is only written before
+    /// actual text, so that blank lines have no trailing whitespace.
+    pending_indent: Option<usize>,
+}
+
+impl Writer {
+    fn column(&self) -> usize {
+        self.pending_indent.unwrap_or(self.col)
+    }
+
+    fn text(&mut self, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+        if let Some(indent) = self.pending_indent.take() {
+            self.out.extend(std::iter::repeat(' ').take(indent));
+            self.col = indent;
+        }
+        self.out.push_str(s);
+        self.col += s.chars().count();
+    }
+
+    fn newline(&mut self, indent: usize) {
+        self.out.push('\n');
+        self.col = 0;
+        self.pending_indent = Some(indent);
+    }
+}
+
+fn render(doc: &Doc, width: usize) -> String {
+    let mut w = Writer {
+        out: String::new(),
+        col: 0,
+        pending_indent: None,
+    };
+    let mut stack = vec![(0, Mode::Break, doc)];
+    while let Some((indent, mode, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => w.text(s),
+            Doc::Line(flat) if mode == Mode::Flat => w.text(flat),
+            Doc::Line(_) | Doc::HardLine => w.newline(indent),
+            Doc::Alt(flat, broken) => {
+                let doc = if mode == Mode::Flat { flat } else { broken };
+                stack.push((indent, mode, doc))
+            }
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|d| (indent, mode, d)))
+            }
+            Doc::Nest(i, doc) => stack.push((indent + i, mode, doc)),
+            Doc::Align(doc) => stack.push((w.column(), mode, doc)),
+            Doc::Group(doc) => {
+                let remaining = width as isize - w.column() as isize;
+                let mode = if mode == Mode::Flat || fits(remaining, doc, &stack)
+                {
+                    Mode::Flat
+                } else {
+                    Mode::Break
+                };
+                stack.push((indent, mode, doc))
+            }
+        }
+    }
+    w.out
+}
+
+/// Whether `doc` laid out flat, followed by `rest` up to its next line break,
+/// fits in `remaining` columns.
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+    let mut stack = vec![(Mode::Flat, doc)];
+    let mut rest = rest.iter().rev();
+    while remaining >= 0 {
+        let (mode, doc) = match stack.pop() {
+            Some(x) => x,
+            None => match rest.next() {
+                Some(&(_, mode, doc)) => (mode, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => remaining -= text_width(s),
+            Doc::Line(flat) if mode == Mode::Flat => {
+                remaining -= text_width(flat)
+            }
+            Doc::Line(_) => return true,
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::Alt(flat, broken) => {
+                let doc = if mode == Mode::Flat { flat } else { broken };
+                stack.push((mode, doc))
+            }
+            Doc::Concat(docs) => {
+                stack.extend(docs.iter().rev().map(|d| (mode, d)))
+            }
+            Doc::Nest(_, doc) | Doc::Align(doc) | Doc::Group(doc) => {
+                stack.push((mode, doc))
+            }
+        }
+    }
+    false
+}
+
+/// Lays out `items` between `open` and `close`, either on one line or with
+/// one item per line and leading separators:
+///
+/// ```text
+/// { a = 1, b = 2 }
+///
+/// { a = 1
+/// , b = 2
+/// }
+/// ```
+fn enclosed(open: &str, sep: &str, close: &str, items: Vec<Doc>) -> Doc {
+    // Commas hug the preceding item when flat, other separators don't
+    let sep_flat = if sep == "," { "" } else { " " };
+    let mut docs = vec![text(format!("{} ", open))];
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            docs.push(Doc::Line(sep_flat));
+            docs.push(text(format!("{} ", sep)));
+        }
+        docs.push(align(item));
+    }
+    docs.push(line());
+    docs.push(text(close));
+    group(align(cat(docs)))
+}
+
+/// A record or union entry, with the value moved to the next line if it
+/// does not fit after the key.
+fn entry(key: &Label, sep: &str, value: Option<Doc>) -> Doc {
+    match value {
+        None => text(key.to_string()),
+        Some(value) => align(group(cat(vec![
+            text(format!("{} {}", key, sep)),
+            nest(2, cat(vec![line(), value])),
+        ]))),
+    }
+}
+
+fn expr_doc<S: Clone, A: Display + Clone>(
+    e: &Expr<S, A>,
+    phase: PrintPhase,
+    cs: CharacterSet,
+) -> Doc {
+    ensure_sufficient_stack(|| {
+        let (needs_paren, phased) = e.phased_subexprs(phase, cs);
+        let doc = match e {
+            ExprF::Lam(_, _, _) | ExprF::Pi(_, _, _) => arrows_doc(e, cs),
+            ExprF::Let(_, _, _, _) => lets_doc(e, cs),
+            ExprF::BinOp(op, _, _) => binops_doc(*op, e, cs),
+            ExprF::App(_, _) => apps_doc(e, cs),
+            _ => phased_doc(phased),
+        };
+        if needs_paren {
+            cat(vec![text("("), align(doc), text(")")])
+        } else {
+            doc
+        }
+    })
+}
+
+fn sub_doc<S: Clone, A: Display + Clone>(e: &PhasedExpr<S, A>) -> Doc {
+    expr_doc(e.0.as_ref(), e.1, e.2)
+}
+
+/// Chains of functions and function types get one arrow per line:
+///
+/// ```text
+///   λ(x : Natural)
+/// → λ(y : Natural)
+/// → x + y
+/// ```
+fn arrows_doc<S: Clone, A: Display + Clone>(
+    mut e: &Expr<S, A>,
+    cs: CharacterSet,
+) -> Doc {
+    let mut docs = vec![alt(text(""), text("  "))];
+    loop {
+        let (head, body) = match e {
+            ExprF::Lam(l, t, b) => (
+                cat(vec![
+                    text(format!("{}({} : ", cs.lambda(), NonReservedLabel(l))),
+                    align(expr_doc(t.as_ref(), PrintPhase::Base, cs)),
+                    text(")"),
+                ]),
+                b,
+            ),
+            ExprF::Pi(l, t, b) if &String::from(l) == "_" => {
+                (expr_doc(t.as_ref(), PrintPhase::Operator, cs), b)
+            }
+            ExprF::Pi(l, t, b) => (
+                cat(vec![
+                    text(format!("{}({} : ", cs.forall(), NonReservedLabel(l))),
+                    align(expr_doc(t.as_ref(), PrintPhase::Base, cs)),
+                    text(")"),
+                ]),
+                b,
+            ),
+            _ => {
+                docs.push(align(expr_doc(e, PrintPhase::Base, cs)));
+                break;
+            }
+        };
+        docs.push(head);
+        docs.push(line());
+        docs.push(text(format!("{} ", cs.arrow())));
+        e = body.as_ref();
+    }
+    group(align(cat(docs)))
+}
+
+/// Nested `let`s are printed as a block:
+///
+/// ```text
+/// let x = 1
+///
+/// let y = 2
+///
+/// in  x + y
+/// ```
+fn lets_doc<S: Clone, A: Display + Clone>(
+    mut e: &Expr<S, A>,
+    cs: CharacterSet,
+) -> Doc {
+    let mut docs = Vec::new();
+    while let ExprF::Let(l, t, v, b) = e {
+        let mut binding = vec![text(format!("let {}", NonReservedLabel(l)))];
+        if let Some(t) = t {
+            binding.push(text(" : "));
+            binding.push(align(expr_doc(t.as_ref(), PrintPhase::Base, cs)));
+        }
+        binding.push(text(" ="));
+        binding.push(nest(
+            2,
+            cat(vec![line(), expr_doc(v.as_ref(), PrintPhase::Base, cs)]),
+        ));
+        docs.push(group(cat(binding)));
+        docs.push(alt(text(" in "), cat(vec![softline(), softline()])));
+        e = b.as_ref();
+    }
+    docs.pop();
+    docs.push(alt(
+        text(" in "),
+        cat(vec![softline(), softline(), text("in  ")]),
+    ));
+    docs.push(align(expr_doc(e, PrintPhase::Base, cs)));
+    group(align(cat(docs)))
+}
+
+/// Chains of the same operator get one operand per line:
+///
+/// ```text
+///     a
+/// ++  b
+/// ++  c
+/// ```
+fn binops_doc<S: Clone, A: Display + Clone>(
+    op: BinOp,
+    mut e: &Expr<S, A>,
+    cs: CharacterSet,
+) -> Doc {
+    let mut operands = Vec::new();
+    loop {
+        match e {
+            ExprF::BinOp(o, a, b) if *o == op => {
+                operands.push(b.as_ref());
+                e = a.as_ref();
+            }
+            _ => {
+                operands.push(e);
+                break;
+            }
+        }
+    }
+    let symbol = op.symbol(cs);
+    let pad = std::cmp::max(4 - text_width(symbol), 1) as usize;
+    let mut docs = vec![alt(
+        text(""),
+        text(" ".repeat(text_width(symbol) as usize + pad)),
+    )];
+    for (i, operand) in operands.into_iter().rev().enumerate() {
+        if i > 0 {
+            docs.push(line());
+            docs.push(text(symbol));
+            docs.push(alt(text(" "), text(" ".repeat(pad))));
+        }
+        let phase = if i == 0 {
+            PrintPhase::BinOp(op)
+        } else {
+            PrintPhase::BinOpRight(op)
+        };
+        docs.push(align(expr_doc(operand, phase, cs)));
+    }
+    group(align(cat(docs)))
+}
+
+/// Applications get one argument per line:
+///
+/// ```text
+/// f
+///   a
+///   b
+/// ```
+fn apps_doc<S: Clone, A: Display + Clone>(
+    mut e: &Expr<S, A>,
+    cs: CharacterSet,
+) -> Doc {
+    let mut args = Vec::new();
+    while let ExprF::App(f, a) = e {
+        args.push(a.as_ref());
+        e = f.as_ref();
+    }
+    let args = args
+        .into_iter()
+        .rev()
+        .map(|a| cat(vec![line(), expr_doc(a, PrintPhase::Import, cs)]))
+        .collect();
+    group(align(cat(vec![
+        expr_doc(e, PrintPhase::Import, cs),
+        nest(2, cat(args)),
+    ])))
+}
+
+fn phased_doc<S: Clone, A: Display + Clone>(
+    e: ExprF<PhasedExpr<S, A>, A>,
+) -> Doc {
+    use crate::ExprF::*;
+    match e {
+        BoolIf(a, b, c) => group(align(cat(vec![
+            text("if "),
+            align(sub_doc(&a)),
+            line(),
+            text("then "),
+            align(sub_doc(&b)),
+            line(),
+            text("else "),
+            align(sub_doc(&c)),
+        ]))),
+        Annot(a, b) => group(align(cat(vec![
+            alt(text(""), text("  ")),
+            align(sub_doc(&a)),
+            line(),
+            text(": "),
+            align(sub_doc(&b)),
+        ]))),
+        Merge(a, b, c) => {
+            let mut docs = vec![
+                text("merge"),
+                nest(2, cat(vec![line(), sub_doc(&a), line(), sub_doc(&b)])),
+            ];
+            if let Some(c) = c {
+                docs.push(line());
+                docs.push(text(": "));
+                docs.push(align(sub_doc(&c)));
+            }
+            group(align(cat(docs)))
+        }
+        EmptyListLit(t) => cat(vec![text("[] : List "), sub_doc(&t)]),
+        NEListLit(es) => {
+            enclosed("[", ",", "]", es.iter().map(sub_doc).collect())
+        }
+        SomeLit(e) => cat(vec![text("Some "), sub_doc(&e)]),
+        Field(a, b) => cat(vec![sub_doc(&a), text(format!(".{}", b))]),
+        Projection(e, ls) => cat(vec![
+            sub_doc(&e),
+            text(format!(".{{ {} }}", ls.iter().join(", "))),
+        ]),
+        TextLit(t) => match multiline_text_doc(&t) {
+            Some(doc) => doc,
+            None => text(t.to_string()),
+        },
+        RecordType(a) if a.is_empty() => text("{}"),
+        RecordType(a) => enclosed(
+            "{",
+            ",",
+            "}",
+            a.iter()
+                .map(|(k, t)| entry(k, ":", Some(sub_doc(t))))
+                .collect(),
+        ),
+        RecordLit(a) if a.is_empty() => text("{=}"),
+        RecordLit(a) => enclosed(
+            "{",
+            ",",
+            "}",
+            a.iter()
+                .map(|(k, v)| entry(k, "=", Some(sub_doc(v))))
+                .collect(),
+        ),
+        UnionType(a) if a.is_empty() => text("<>"),
+        UnionType(a) => enclosed(
+            "<",
+            "|",
+            ">",
+            a.iter()
+                .map(|(k, t)| entry(k, ":", t.as_ref().map(sub_doc)))
+                .collect(),
+        ),
+        UnionLit(k, v, a) => enclosed(
+            "<",
+            "|",
+            ">",
+            std::iter::once(entry(&k, "=", Some(sub_doc(&v))))
+                .chain(
+                    a.iter()
+                        .map(|(k, t)| entry(k, ":", t.as_ref().map(sub_doc))),
+                )
+                .collect(),
+        ),
+        // Uses the ExprF<PhasedExpr<_>, _> instance. None of the remaining
+        // expressions contain a character that has an ASCII form.
+        e => text(e.to_string()),
+    }
+}
+
+/// Text containing newlines is printed as a multi-line literal:
+///
+/// ```text
+/// ''
+/// first line
+/// second line
+/// ''
+/// ```
+///
+/// Returns `None` if the text cannot be represented faithfully that way.
+fn multiline_text_doc<S: Clone, A: Display + Clone>(
+    t: &InterpolatedText<PhasedExpr<S, A>>,
+) -> Option<Doc> {
+    let chunks: Vec<_> = t.iter().collect();
+    let texts = || {
+        chunks.iter().filter_map(|c| match c {
+            InterpolatedTextContents::Text(s) => Some(s.as_str()),
+            InterpolatedTextContents::Expr(_) => None,
+        })
+    };
+    if !texts().any(|s| s.contains('\n')) {
+        return None;
+    }
+    // Only newlines can be written as-is; everything else would need escapes
+    // that multi-line literals do not have.
+    if texts().any(|s| s.chars().any(|c| c.is_control() && c != '\n')) {
+        return None;
+    }
+    // The common indentation of all lines gets stripped when parsing, so
+    // some line must start at the indentation of the closing quotes.
+    let mut unindented_line = false;
+    let mut at_line_start = true;
+    for c in &chunks {
+        match c {
+            InterpolatedTextContents::Text(s) => {
+                for l in s.split('\n').skip(if at_line_start { 0 } else { 1 }) {
+                    if !l.is_empty() && !l.starts_with(' ') {
+                        unindented_line = true;
+                    }
+                }
+                at_line_start =
+                    s.ends_with('\n') || (at_line_start && s.is_empty());
+            }
+            InterpolatedTextContents::Expr(_) => {
+                unindented_line |= at_line_start;
+                at_line_start = false;
+            }
+        }
+    }
+    // The last line is followed by the closing quotes.
+    unindented_line |= at_line_start;
+    if !unindented_line {
+        return None;
+    }
+    // A trailing quote would merge with the closing quotes.
+    if !at_line_start && texts().last().map_or(false, |s| s.ends_with('\'')) {
+        return None;
+    }
+    // Escaping would turn `'${` into `'''${`, which reads back as an escaped
+    // `''` followed by an interpolation.
+    if texts().any(|s| s.contains("'${")) {
+        return None;
+    }
+
+    let mut docs = vec![text("''"), Doc::HardLine];
+    for c in chunks {
+        match c {
+            InterpolatedTextContents::Text(s) => {
+                let s = s.replace("''", "'''").replace("${", "''${");
+                for (i, l) in s.split('\n').enumerate() {
+                    if i > 0 {
+                        docs.push(Doc::HardLine);
+                    }
+                    docs.push(text(l));
+                }
+            }
+            InterpolatedTextContents::Expr(e) => {
+                docs.push(text("${"));
+                docs.push(sub_doc(e));
+                docs.push(text("}"));
+            }
+        }
+    }
+    docs.push(text("''"));
+    Some(align(cat(docs)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FormatOptions;
+    use crate::parse_expr;
+    use crate::CharacterSet;
+
+    fn format(s: &str, width: usize) -> String {
+        let e = parse_expr(s).unwrap();
+        let formatted = e.format(&FormatOptions {
+            width,
+            ..FormatOptions::default()
+        });
+        assert_eq!(parse_expr(&formatted).unwrap(), e, "{}", formatted);
+        formatted
+    }
+
+    #[test]
+    fn short_expressions_stay_on_one_line() {
+        assert_eq!(
+            format("{ a = 1, b = [ 1, 2 ] }", 80),
+            "{ a = 1, b = [ 1, 2 ] }"
+        );
+        assert_eq!(
+            format("λ(x : Natural) → x + 1", 80),
+            "λ(x : Natural) → x + 1"
+        );
+    }
+
+    #[test]
+    fn long_expressions_are_broken() {
+        assert_eq!(
+            format("{ foo = 1, bar = [ \"a\", \"b\" ], baz = True }", 20),
+            "{ foo = 1\n, bar = [ \"a\", \"b\" ]\n, baz = True\n}"
+        );
+        assert_eq!(
+            format("let x = 1 let y = 2 in x + y", 10),
+            "let x = 1\n\nlet y = 2\n\nin  x + y"
+        );
+        assert_eq!(
+            format("λ(x : Natural) → λ(y : Natural) → x", 20),
+            "  λ(x : Natural)\n→ λ(y : Natural)\n→ x"
+        );
+    }
+
+    #[test]
+    fn ascii() {
+        let e = parse_expr("λ(x : ∀(a : Type) → a) → x ⫽ { b = 1 }")
+            .unwrap();
+        let expected = "\\(x : forall(a : Type) -> a) -> x // { b = 1 }";
+        let options = FormatOptions {
+            charset: CharacterSet::Ascii,
+            ..FormatOptions::default()
+        };
+        assert_eq!(e.format(&options), expected);
+        assert_eq!(e.display_with(CharacterSet::Ascii).to_string(), expected);
+        assert_eq!(parse_expr(expected).unwrap(), e);
+    }
+
+    #[test]
+    fn multiline_text() {
+        assert_eq!(
+            format("{ a = \"one\\ntwo ${x}''\\n\" }", 80),
+            "{ a =\n    ''\n    one\n    two ${x}'''\n    ''\n}"
+        );
+        // Every line is indented
+        format("\"  one\\n  two\"", 80);
+        // `'${` has no escape in multi-line literals
+        assert_eq!(
+            format("\"one\\ntwo '\\${x}\"", 80),
+            "\"one\\ntwo '\\u0024{x}\""
+        );
+    }
+}
@@ -18,5 +18,14 @@ pub use crate::core::visitor;
 pub use crate::core::*;
 mod printer;
 pub use crate::printer::*;
+mod format;
+pub use crate::format::*;
 mod parser;
 pub use crate::parser::*;
+mod parse_error;
+pub use crate::parse_error::*;
+mod recovery;
+pub use crate::recovery::*;
+pub mod cst;
+pub mod stack;
+pub mod sync;
@@ -0,0 +1,341 @@
+use pest::error::{ErrorVariant, InputLocation, LineColLocation};
+use std::fmt::{self, Display};
+
+use dhall_generated_parser::Rule;
+
+/// A syntax error.
+///
+/// The error is described in terms of the language rather than of the rules
+/// of the grammar, and comes with a suggested fix for common mistakes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+    help: Option<String>,
+    /// Keeps track of the location, and prints the offending line.
+    pest: pest::error::Error<Rule>,
+}
+
+impl ParseError {
+    pub(crate) fn new(e: pest::error::Error<Rule>, input: &str) -> Self {
+        let pos = match e.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+        let help = suggest_fix(&e.variant, input, pos);
+        ParseError::at(input, pos, friendly_message(&e.variant), help)
+    }
+
+    fn at(
+        input: &str,
+        pos: usize,
+        message: String,
+        help: Option<String>,
+    ) -> Self {
+        let variant = ErrorVariant::CustomError {
+            message: message.clone(),
+        };
+        let pos = pest::Position::new(input, pos).unwrap();
+        ParseError {
+            message,
+            help,
+            pest: pest::error::Error::new_from_pos(variant, pos),
+        }
+    }
+
+    /// The same error, at offset `pos` of `input`.
+    pub(crate) fn moved_to(self, input: &str, pos: usize) -> Self {
+        ParseError::at(input, pos, self.message, self.help)
+    }
+
+    /// Mentions `path` as the file the error is in.
+    pub fn with_file_name(mut self, path: &str) -> Self {
+        self.pest = self.pest.with_path(path);
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// A suggested fix, if the error looks like a common mistake.
+    pub fn help(&self) -> Option<&str> {
+        self.help.as_ref().map(String::as_str)
+    }
+
+    /// The byte offset of the error in the input.
+    pub fn offset(&self) -> usize {
+        match self.pest.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        }
+    }
+
+    /// The line and column of the error, starting from 1.
+    pub fn line_col(&self) -> (usize, usize) {
+        match self.pest.line_col {
+            LineColLocation::Pos(lc) => lc,
+            LineColLocation::Span(lc, _) => lc,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.pest)?;
+        if let Some(help) = &self.help {
+            write!(f, "\n  = help: {}", help)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Describes an error in terms of the language rather than of the grammar.
+fn friendly_message(e: &ErrorVariant<Rule>) -> String {
+    let positives = match e {
+        ErrorVariant::ParsingError { positives, .. } => positives,
+        ErrorVariant::CustomError { message } => return message.clone(),
+    };
+    if positives.contains(&Rule::in_) {
+        return "expected `in` after let binding".to_owned();
+    }
+    let mut expected: Vec<&str> = Vec::new();
+    for r in positives {
+        let d = describe_rule(*r);
+        if !expected.contains(&d) {
+            expected.push(d);
+        }
+    }
+    match expected.split_last() {
+        None => "unexpected input".to_owned(),
+        Some((last, [])) => format!("expected {}", last),
+        Some((last, init)) => {
+            format!("expected {} or {}", init.join(", "), last)
+        }
+    }
+}
+
+fn describe_rule(r: Rule) -> &'static str {
+    use Rule::*;
+    match r {
+        EOI => "end of input",
+        in_ => "`in`",
+        if_ => "`if`",
+        merge => "`merge`",
+        missing => "`missing`",
+        Some_ => "`Some`",
+        lambda => "`λ`",
+        forall => "`∀`",
+        arrow => "`→`",
+        label | simple_label | quoted_label | labels => "a label",
+        identifier | variable => "a variable",
+        builtin | Optional | Text | List => "a builtin",
+        double_quote_literal
+        | double_quote_chunk
+        | double_quote_char
+        | double_quote_escaped
+        | single_quote_literal
+        | single_quote_continue
+        | single_quote_char
+        | escaped_quote_pair
+        | escaped_interpolation => "text",
+        interpolation => "an interpolation",
+        natural_literal
+        | integer_literal
+        | double_literal
+        | numeric_double_literal
+        | exponent
+        | NaN
+        | minus_infinity_literal
+        | plus_infinity_literal => "a number",
+        import | import_hashed | import_type | local | http | http_raw
+        | env | path | path_component | parent_path | here_path | home_path
+        | absolute_path => "an import",
+        hash => "an import hash",
+        let_binding => "a let binding",
+        selector | selector_expression => "a field access",
+        empty_record_literal
+        | empty_record_type
+        | non_empty_record_type_or_literal
+        | non_empty_record_type
+        | non_empty_record_literal
+        | record_type_entry
+        | record_literal_entry => "a record field",
+        union_type_or_literal
+        | empty_union_type
+        | non_empty_union_type_or_literal
+        | union_literal_variant_value
+        | union_type_entry
+        | union_type_or_literal_variant_type => "a union alternative",
+        non_empty_list_literal | empty_collection | non_empty_optional => {
+            "a list"
+        }
+        _ => "an expression",
+    }
+}
+
+/// Characters that are easily mistaken for the ASCII ones Dhall expects,
+/// e.g. when copying code from a word processor.
+fn ascii_lookalike(c: char) -> Option<char> {
+    match c {
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{FF02}' => Some('"'),
+        '\u{2018}' | '\u{2019}' | '\u{FF07}' => Some('\''),
+        '\u{2010}'..='\u{2015}' | '\u{2212}' | '\u{FF0D}' => Some('-'),
+        '\u{00A0}' | '\u{2000}'..='\u{200B}' | '\u{3000}' => Some(' '),
+        '\u{FF08}' => Some('('),
+        '\u{FF09}' => Some(')'),
+        '\u{FF0C}' | '\u{201A}' => Some(','),
+        '\u{FF1A}' => Some(':'),
+        '\u{FF1D}' => Some('='),
+        '\u{FF5B}' => Some('{'),
+        '\u{FF5D}' => Some('}'),
+        _ => None,
+    }
+}
+
+fn suggest_fix(
+    e: &ErrorVariant<Rule>,
+    input: &str,
+    pos: usize,
+) -> Option<String> {
+    let found = input[pos..].chars().next();
+    if let Some(ascii) = found.and_then(ascii_lookalike) {
+        let c = found.unwrap();
+        return Some(format!(
+            "`{}` (U+{:04X}) looks like `{}` but is a different character; \
+             use `{}` instead",
+            c, c as u32, ascii, ascii
+        ));
+    }
+    if let ErrorVariant::ParsingError { positives, .. } = e {
+        if positives.contains(&Rule::in_) {
+            return Some(
+                "a `let` binding must be followed by `in` and then the \
+                 expression that uses it, as in `let x = 1 in x`"
+                    .to_owned(),
+            );
+        }
+    }
+    if let Some(help) = suggest_record_separator(input, pos) {
+        return Some(help.to_owned());
+    }
+    if is_in_unclosed_interpolation(input, pos) {
+        return Some(
+            "an interpolation started with `${` is missing its \
+             closing `}`"
+                .to_owned(),
+        );
+    }
+    None
+}
+
+/// Catches records that mix up `=` and `:`, like `{ x = 1, y : Natural }`,
+/// by looking at the separator used by the previous fields.
+fn suggest_record_separator(input: &str, pos: usize) -> Option<&'static str> {
+    let found = input[pos..].chars().next()?;
+    if found != '=' && found != ':' {
+        return None;
+    }
+    // Find the brace that opens the record
+    let before = &input[..pos];
+    let mut depth = 0;
+    let mut open = None;
+    for (i, c) in before.char_indices().rev() {
+        match c {
+            '}' | ')' | ']' => depth += 1,
+            '{' | '(' | '[' if depth > 0 => depth -= 1,
+            '{' => {
+                open = Some(i);
+                break;
+            }
+            '(' | '[' => return None,
+            _ => {}
+        }
+    }
+    let mut depth = 0;
+    let mut separator = None;
+    for c in before[open? + 1..].chars() {
+        match c {
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => depth -= 1,
+            '=' | ':' if depth == 0 => {
+                separator = Some(c);
+                break;
+            }
+            _ => {}
+        }
+    }
+    match (separator?, found) {
+        ('=', ':') => Some(
+            "the other fields are separated from their values by `=`; in a \
+             record literal every field uses `=`, as in `{ x = 1 }`",
+        ),
+        (':', '=') => Some(
+            "the other fields are separated from their types by `:`; in a \
+             record type every field uses `:`, as in `{ x : Natural }`",
+        ),
+        _ => None,
+    }
+}
+
+/// Whether the last `${` before `pos` is never closed.
+fn is_in_unclosed_interpolation(input: &str, pos: usize) -> bool {
+    let start = match input[..pos].rfind("${") {
+        Some(i) => i + 2,
+        None => return false,
+    };
+    let mut depth = 1;
+    for c in input[start..].chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_expr;
+
+    fn help(s: &str) -> String {
+        parse_expr(s).unwrap_err().help().unwrap().to_owned()
+    }
+
+    #[test]
+    fn missing_in() {
+        let e = parse_expr("let x = 1 x").unwrap_err();
+        assert_eq!(e.message(), "expected `in` after let binding");
+        assert!(e.help().unwrap().contains("`in`"));
+    }
+
+    #[test]
+    fn record_separators() {
+        assert!(help("{ x = 1, y : Natural }").contains("record literal"));
+        assert!(help("{ x : Natural, y = 1 }").contains("record type"));
+    }
+
+    #[test]
+    fn unclosed_interpolation() {
+        assert!(help("\"a ${x\"").contains("`${`"));
+    }
+
+    #[test]
+    fn lookalikes() {
+        assert!(help("\u{201C}a\u{201D}").contains("U+201C"));
+    }
+
+    #[test]
+    fn file_name() {
+        let e = parse_expr("1 +").unwrap_err().with_file_name("foo.dhall");
+        assert!(e.to_string().contains("foo.dhall:1:"));
+        assert_eq!(e.line_col().0, 1);
+    }
+}
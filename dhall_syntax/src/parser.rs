@@ -1,12 +1,13 @@
 use itertools::Itertools;
+use num_traits::ToPrimitive;
 use pest::iterators::Pair;
 use pest::Parser;
 use std::borrow::Cow;
-use std::rc::Rc;
 
 use dhall_generated_parser::{DhallParser, Rule};
 
 use crate::map::DupTreeMap;
+use crate::sync::Rc;
 use crate::ExprF::*;
 use crate::*;
 
@@ -20,10 +21,10 @@ type ParsedSubExpr = SubExpr<Span, Import>;
 type ParsedText = InterpolatedText<SubExpr<Span, Import>>;
 type ParsedTextContents = InterpolatedTextContents<SubExpr<Span, Import>>;
 
-pub type ParseError = pest::error::Error<Rule>;
-
 pub type ParseResult<T> = Result<T, ParseError>;
 
+type PestError = pest::error::Error<Rule>;
+
 fn unspanned(x: ParsedExpr) -> ParsedSubExpr {
     SubExpr::from_expr_no_note(x)
 }
@@ -49,6 +50,20 @@ impl Span {
             end: sp.end(),
         }
     }
+
+    /// The same span in another version of the input, given where each
+    /// offset of the current input ended up.
+    pub(crate) fn remap(
+        &self,
+        input: &Rc<str>,
+        offset: impl Fn(usize) -> usize,
+    ) -> Self {
+        Span {
+            input: input.clone(),
+            start: offset(self.start),
+            end: offset(self.end),
+        }
+    }
 }
 
 fn spanned(span: Span, x: ParsedExpr) -> ParsedSubExpr {
@@ -98,7 +113,7 @@ impl crate::Builtin {
     }
 }
 
-pub fn custom_parse_error(pair: &Pair<Rule>, msg: String) -> ParseError {
+fn custom_parse_error(pair: &Pair<Rule>, msg: String) -> PestError {
     let msg =
         format!("{} while matching on:\n{}", msg, debug_pair(pair.clone()));
     let e = pest::error::ErrorVariant::CustomError { message: msg };
@@ -253,7 +268,7 @@ macro_rules! make_parser {
 fn do_parse<'a>(
     input: Rc<str>,
     initial_pair: Pair<'a, Rule>,
-) -> ParseResult<ParsedValue<'a>> {
+) -> Result<ParsedValue<'a>, PestError> {
     enum StackFrame<'a> {
         Unprocessed(Pair<'a, Rule>),
         Processed(Pair<'a, Rule>, usize),
@@ -403,11 +418,14 @@ make_parser! {
                 "r" => "\r".to_owned(),
                 "t" => "\t".to_owned(),
                 _ => {
-                    // "uXXXX"
-                    use std::convert::TryFrom;
-                    let c = u16::from_str_radix(&s[1..5], 16).unwrap();
-                    let c = char::try_from(u32::from(c)).unwrap();
-                    std::iter::once(c).collect()
+                    // "uXXXX" or "u{XXXXXX}"
+                    match unescape_unicode(&s[1..]) {
+                        Some(c) => std::iter::once(c).collect(),
+                        None => Err(format!(
+                            "invalid unicode escape sequence: \\{}",
+                            s
+                        ))?,
+                    }
                 }
             }
         }
@@ -539,6 +557,9 @@ make_parser! {
 
     rule!(variable<V<Label>>; children!(
         [label(l), natural_literal(idx)] => {
+            let idx = idx.to_usize().ok_or_else(|| {
+                format!("variable index is too large: {}", idx)
+            })?;
             V(l, idx)
         },
         [label(l)] => {
@@ -1004,9 +1025,11 @@ make_parser! {
 }
 
 pub fn parse_expr(s: &str) -> ParseResult<ParsedSubExpr> {
-    let mut pairs = DhallParser::parse(Rule::final_expression, s)?;
+    let mut pairs = DhallParser::parse(Rule::final_expression, s)
+        .map_err(|e| ParseError::new(e, s))?;
     let rc_input = s.to_string().into();
-    let expr = do_parse(rc_input, pairs.next().unwrap())?;
+    let expr = do_parse(rc_input, pairs.next().unwrap())
+        .map_err(|e| ParseError::new(e, s))?;
     assert_eq!(pairs.next(), None);
     match expr {
         ParsedValue::expression(e) => Ok(e),
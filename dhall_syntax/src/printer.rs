@@ -1,26 +1,90 @@
+use crate::stack::ensure_sufficient_stack;
 use crate::*;
 use itertools::Itertools;
 use std::fmt::{self, Display};
 
+/// The characters to print the keywords and operators that have both a
+/// Unicode and an ASCII form with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharacterSet {
+    Unicode,
+    Ascii,
+}
+
+impl Default for CharacterSet {
+    fn default() -> Self {
+        CharacterSet::Unicode
+    }
+}
+
+impl CharacterSet {
+    pub(crate) fn lambda(self) -> &'static str {
+        match self {
+            CharacterSet::Unicode => "λ",
+            CharacterSet::Ascii => "\\",
+        }
+    }
+
+    pub(crate) fn arrow(self) -> &'static str {
+        match self {
+            CharacterSet::Unicode => "→",
+            CharacterSet::Ascii => "->",
+        }
+    }
+
+    pub(crate) fn forall(self) -> &'static str {
+        match self {
+            CharacterSet::Unicode => "∀",
+            CharacterSet::Ascii => "forall",
+        }
+    }
+}
+
 /// Generic instance that delegates to subexpressions
 impl<SE: Display + Clone, E: Display> Display for ExprF<SE, E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        self.fmt_with(f, CharacterSet::Unicode)
+    }
+}
+
+impl<SE: Display + Clone, E: Display> ExprF<SE, E> {
+    fn fmt_with(
+        &self,
+        f: &mut fmt::Formatter,
+        cs: CharacterSet,
+    ) -> Result<(), fmt::Error> {
         use crate::ExprF::*;
         match self {
             Lam(a, b, c) => {
-                write!(f, "λ({} : {}) → {}", a, b, c)?;
+                write!(
+                    f,
+                    "{}({} : {}) {} {}",
+                    cs.lambda(),
+                    NonReservedLabel(a),
+                    b,
+                    cs.arrow(),
+                    c
+                )?;
             }
             BoolIf(a, b, c) => {
                 write!(f, "if {} then {} else {}", a, b, c)?;
             }
             Pi(a, b, c) if &String::from(a) == "_" => {
-                write!(f, "{} → {}", b, c)?;
+                write!(f, "{} {} {}", b, cs.arrow(), c)?;
             }
             Pi(a, b, c) => {
-                write!(f, "∀({} : {}) → {}", a, b, c)?;
+                write!(
+                    f,
+                    "{}({} : {}) {} {}",
+                    cs.forall(),
+                    NonReservedLabel(a),
+                    b,
+                    cs.arrow(),
+                    c
+                )?;
             }
             Let(a, b, c, d) => {
-                write!(f, "let {}", a)?;
+                write!(f, "let {}", NonReservedLabel(a))?;
                 if let Some(b) = b {
                     write!(f, " : {}", b)?;
                 }
@@ -51,7 +115,7 @@ impl<SE: Display + Clone, E: Display> Display for ExprF<SE, E> {
                 write!(f, "{} : {}", a, b)?;
             }
             ExprF::BinOp(op, a, b) => {
-                write!(f, "{} {} {}", a, op, b)?;
+                write!(f, "{} {} {}", a, op.symbol(cs), b)?;
             }
             ExprF::App(a, b) => {
                 write!(f, "{} {}", a, b)?;
@@ -63,13 +127,13 @@ impl<SE: Display + Clone, E: Display> Display for ExprF<SE, E> {
                 write!(f, "{}.", e)?;
                 fmt_list("{ ", ", ", " }", ls, f, Display::fmt)?;
             }
-            Var(a) => a.fmt(f)?,
+            Var(V(a, n)) => V(NonReservedLabel(a), *n).fmt(f)?,
             Const(k) => k.fmt(f)?,
             Builtin(v) => v.fmt(f)?,
             BoolLit(true) => f.write_str("True")?,
             BoolLit(false) => f.write_str("False")?,
             NaturalLit(a) => a.fmt(f)?,
-            IntegerLit(a) if *a >= 0 => {
+            IntegerLit(a) if !a.is_negative() => {
                 f.write_str("+")?;
                 a.fmt(f)?;
             }
@@ -112,10 +176,13 @@ impl<SE: Display + Clone, E: Display> Display for ExprF<SE, E> {
 // the relationship between the corresponding grammar rules. This leads to the nice property
 // of automatically getting all the parentheses and precedences right.
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-enum PrintPhase {
+pub(crate) enum PrintPhase {
     Base,
     Operator,
     BinOp(core::BinOp),
+    /// The right operand of a binary operator. Operators associate to the
+    /// left, so this needs parentheses around the same operator.
+    BinOpRight(core::BinOp),
     App,
     Import,
     Primitive,
@@ -124,26 +191,32 @@ enum PrintPhase {
 // Wraps an Expr with a phase, so that phase selsction can be done
 // separate from the actual printing
 #[derive(Clone)]
-struct PhasedExpr<'a, S, A>(&'a SubExpr<S, A>, PrintPhase);
+pub(crate) struct PhasedExpr<'a, S, A>(
+    pub(crate) &'a SubExpr<S, A>,
+    pub(crate) PrintPhase,
+    pub(crate) CharacterSet,
+);
 
 impl<'a, S: Clone, A: Display + Clone> Display for PhasedExpr<'a, S, A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        self.0.as_ref().fmt_phase(f, self.1)
+        self.0.as_ref().fmt_phase(f, self.1, self.2)
     }
 }
 
 impl<'a, S: Clone, A: Display + Clone> PhasedExpr<'a, S, A> {
     fn phase(self, phase: PrintPhase) -> PhasedExpr<'a, S, A> {
-        PhasedExpr(self.0, phase)
+        PhasedExpr(self.0, phase, self.2)
     }
 }
 
 impl<S: Clone, A: Display + Clone> Expr<S, A> {
-    fn fmt_phase(
+    /// Whether this expression needs parentheses when printed in `phase`,
+    /// along with its subexpressions annotated with the phase to print them in.
+    pub(crate) fn phased_subexprs(
         &self,
-        f: &mut fmt::Formatter,
         phase: PrintPhase,
-    ) -> Result<(), fmt::Error> {
+        cs: CharacterSet,
+    ) -> (bool, ExprF<PhasedExpr<S, A>, A>) {
         use crate::ExprF::*;
         use PrintPhase::*;
 
@@ -163,14 +236,18 @@ impl<S: Clone, A: Display + Clone> Expr<S, A> {
                 true
             }
             // Precedence is magically handled by the ordering of BinOps.
-            ExprF::BinOp(op, _, _) if phase > PrintPhase::BinOp(*op) => true,
+            ExprF::BinOp(op, _, _) => match phase {
+                BinOpRight(o) => o >= *op,
+                _ => phase > PrintPhase::BinOp(*op),
+            },
             ExprF::App(_, _) if phase > PrintPhase::App => true,
             Field(_, _) | Projection(_, _) if phase > Import => true,
             _ => false,
         };
 
         // Annotate subexpressions with the appropriate phase, defaulting to Base
-        let phased_self = match self.map_ref_simple(|e| PhasedExpr(e, Base)) {
+        let phased_self = match self.map_ref_simple(|e| PhasedExpr(e, Base, cs))
+        {
             Pi(a, b, c) => {
                 if &String::from(&a) == "_" {
                     Pi(a, b.phase(Operator), c)
@@ -183,11 +260,28 @@ impl<S: Clone, A: Display + Clone> Expr<S, A> {
                 b.phase(Import),
                 c.map(|x| x.phase(PrintPhase::App)),
             ),
-            Annot(a, b) => Annot(a.phase(Operator), b),
+            Annot(a, b) => {
+                // `[x] : Optional T` would parse back as an old-style Optional
+                // literal
+                let is_old_optional = match (a.0.as_ref(), b.0.as_ref()) {
+                    (NEListLit(xs), App(f, _)) if xs.len() == 1 => {
+                        match f.as_ref() {
+                            Builtin(crate::Builtin::Optional) => true,
+                            _ => false,
+                        }
+                    }
+                    _ => false,
+                };
+                if is_old_optional {
+                    Annot(a.phase(Import), b)
+                } else {
+                    Annot(a.phase(Operator), b)
+                }
+            }
             ExprF::BinOp(op, a, b) => ExprF::BinOp(
                 op,
                 a.phase(PrintPhase::BinOp(op)),
-                b.phase(PrintPhase::BinOp(op)),
+                b.phase(BinOpRight(op)),
             ),
             EmptyListLit(t) => EmptyListLit(t.phase(Import)),
             OldOptionalLit(x, t) => OldOptionalLit(x, t.phase(Import)),
@@ -198,23 +292,45 @@ impl<S: Clone, A: Display + Clone> Expr<S, A> {
             e => e,
         };
 
-        if needs_paren {
-            f.write_str("(")?;
-        }
+        (needs_paren, phased_self)
+    }
 
-        // Uses the ExprF<PhasedExpr<_>, _> instance
-        phased_self.fmt(f)?;
+    fn fmt_phase(
+        &self,
+        f: &mut fmt::Formatter,
+        phase: PrintPhase,
+        cs: CharacterSet,
+    ) -> Result<(), fmt::Error> {
+        ensure_sufficient_stack(|| {
+            let (needs_paren, phased_self) = self.phased_subexprs(phase, cs);
 
-        if needs_paren {
-            f.write_str(")")?;
-        }
-        Ok(())
+            if needs_paren {
+                f.write_str("(")?;
+            }
+
+            // Uses the ExprF<PhasedExpr<_>, _> instance
+            phased_self.fmt_with(f, cs)?;
+
+            if needs_paren {
+                f.write_str(")")?;
+            }
+            Ok(())
+        })
     }
 }
 
 impl<S: Clone, A: Display + Clone> Display for SubExpr<S, A> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        self.as_ref().fmt_phase(f, PrintPhase::Base)
+        self.as_ref()
+            .fmt_phase(f, PrintPhase::Base, CharacterSet::Unicode)
+    }
+}
+
+impl<S: Clone, A: Display + Clone> SubExpr<S, A> {
+    /// Displays the expression using the given characters for keywords and
+    /// operators.
+    pub fn display_with(&self, cs: CharacterSet) -> impl Display + '_ {
+        PhasedExpr(self, PrintPhase::Base, cs)
     }
 }
 
@@ -246,30 +362,7 @@ impl<SubExpr: Display + Clone> Display for InterpolatedText<SubExpr> {
         for x in self.iter() {
             match x {
                 InterpolatedTextContents::Text(a) => {
-                    for c in a.chars() {
-                        match c {
-                            '\\' => f.write_str("\\\\"),
-                            '"' => f.write_str("\\\""),
-                            '$' => f.write_str("\\u0024"),
-                            '\u{0008}' => f.write_str("\\b"),
-                            '\u{000C}' => f.write_str("\\f"),
-                            '\n' => f.write_str("\\n"),
-                            '\r' => f.write_str("\\r"),
-                            '\t' => f.write_str("\\t"),
-                            '\u{0000}'..='\u{001F}' => {
-                                // Escape to an explicit "\u{XXXX}" form
-                                let escaped: String =
-                                    c.escape_default().collect();
-                                // Print as "\uXXXX"
-                                write!(
-                                    f,
-                                    "\\u{:0>4}",
-                                    &escaped[3..escaped.len() - 1]
-                                )
-                            }
-                            c => write!(f, "{}", c),
-                        }?;
-                    }
+                    f.write_str(&escape_text(a))?;
                 }
                 InterpolatedTextContents::Expr(e) => {
                     f.write_str("${ ")?;
@@ -289,23 +382,33 @@ impl Display for Const {
     }
 }
 
+impl BinOp {
+    pub fn symbol(self, cs: CharacterSet) -> &'static str {
+        use crate::BinOp::*;
+        use CharacterSet::*;
+        match (self, cs) {
+            (BoolOr, _) => "||",
+            (TextAppend, _) => "++",
+            (NaturalPlus, _) => "+",
+            (BoolAnd, _) => "&&",
+            (RecursiveRecordMerge, Unicode) => "∧",
+            (RecursiveRecordMerge, Ascii) => "/\\",
+            (NaturalTimes, _) => "*",
+            (BoolEQ, _) => "==",
+            (BoolNE, _) => "!=",
+            (RecursiveRecordTypeMerge, Unicode) => "⩓",
+            (RecursiveRecordTypeMerge, Ascii) => "//\\\\",
+            (ImportAlt, _) => "?",
+            (RightBiasedRecordMerge, Unicode) => "⫽",
+            (RightBiasedRecordMerge, Ascii) => "//",
+            (ListAppend, _) => "#",
+        }
+    }
+}
+
 impl Display for BinOp {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        use crate::BinOp::*;
-        f.write_str(match self {
-            BoolOr => "||",
-            TextAppend => "++",
-            NaturalPlus => "+",
-            BoolAnd => "&&",
-            RecursiveRecordMerge => "∧",
-            NaturalTimes => "*",
-            BoolEQ => "==",
-            BoolNE => "!=",
-            RecursiveRecordTypeMerge => "⩓",
-            ImportAlt => "?",
-            RightBiasedRecordMerge => "⫽",
-            ListAppend => "#",
-        })
+        f.write_str(self.symbol(CharacterSet::Unicode))
     }
 }
 
@@ -331,21 +434,55 @@ impl Display for NaiveDouble {
 
 impl Display for Label {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        // TODO: distinguish between reserved and nonreserved locations for quoting builtins
         let s = String::from(self);
-        let is_reserved = match s.as_str() {
-            "let" | "in" | "if" | "then" | "else" | "Type" | "Kind"
-            | "Sort" | "True" | "False" => true,
-            _ => crate::Builtin::parse(&s).is_some(),
-        };
-        if !is_reserved && s.chars().all(|c| c.is_ascii_alphanumeric()) {
-            write!(f, "{}", s)
+        if is_keyword(&s) || !is_simple_label(&s) {
+            write!(f, "`{}`", s)
         } else {
+            write!(f, "{}", s)
+        }
+    }
+}
+
+/// A label in a position where the grammar does not allow builtin names, i.e.
+/// a variable or a binder. Record fields and union alternatives only need the
+/// quoting done by the `Display` impl of `Label`.
+pub(crate) struct NonReservedLabel<'a>(pub(crate) &'a Label);
+
+impl<'a> Display for NonReservedLabel<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let s = String::from(self.0);
+        if is_builtin(&s) {
             write!(f, "`{}`", s)
+        } else {
+            self.0.fmt(f)
         }
     }
 }
 
+fn is_keyword(s: &str) -> bool {
+    match s {
+        "if" | "then" | "else" | "let" | "in" | "as" | "using" | "merge"
+        | "missing" | "Infinity" | "NaN" | "Some" => true,
+        _ => false,
+    }
+}
+
+fn is_builtin(s: &str) -> bool {
+    match s {
+        "Type" | "Kind" | "Sort" | "True" | "False" => true,
+        _ => crate::Builtin::parse(s).is_some(),
+    }
+}
+
+fn is_simple_label(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '/' || c == '_')
+}
+
 impl Display for Hash {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(f, "{}:{}", self.protocol, self.hash)
@@ -508,3 +645,83 @@ impl Display for X {
         match *self {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use ExprF::*;
+
+    fn label() -> impl Strategy<Value = Label> {
+        let interesting = vec![
+            "x",
+            "_",
+            "if",
+            "let",
+            "Some",
+            "NaN",
+            "Natural",
+            "Natural/fold",
+            "Type",
+            "True",
+            "None",
+            "foo-bar",
+            "a/b",
+            "with space",
+            "1st",
+        ];
+        prop_oneof![
+            prop::sample::select(interesting).prop_map(Label::from),
+            // The characters allowed in quoted labels
+            "[ -_a-~]{1,5}".prop_map(Label::from),
+        ]
+    }
+
+    /// Expressions that exercise labels in every position.
+    fn expr() -> impl Strategy<Value = SubExpr<Span, Import>> {
+        let var = (label(), 0..3usize)
+            .prop_map(|(l, n)| SubExpr::from_expr_no_note(Var(V(l, n))));
+        var.prop_recursive(4, 32, 3, |e| {
+            let fields = prop::collection::vec((label(), e.clone()), 1..3);
+            let alts = prop::collection::vec(
+                (label(), prop::option::of(e.clone())),
+                1..3,
+            );
+            prop_oneof![
+                (label(), e.clone(), e.clone())
+                    .prop_map(|(l, t, b)| Lam(l, t, b)),
+                (label(), e.clone(), e.clone())
+                    .prop_map(|(l, t, b)| Pi(l, t, b)),
+                (label(), e.clone(), e.clone())
+                    .prop_map(|(l, v, b)| Let(l, None, v, b)),
+                fields.prop_map(|kvs| RecordLit(kvs.into_iter().collect())),
+                alts.prop_map(|kvs| UnionType(kvs.into_iter().collect())),
+                (e.clone(), label()).prop_map(|(e, l)| Field(e, l)),
+                (e, prop::collection::vec(label(), 1..3))
+                    .prop_map(|(e, ls)| Projection(e, ls)),
+            ]
+            .prop_map(SubExpr::from_expr_no_note)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn parse_print_round_trip(e in expr()) {
+            let printed = e.to_string();
+            prop_assert_eq!(parse_expr(&printed).ok(), Some(e.clone()));
+            let opts = FormatOptions { width: 20, ..Default::default() };
+            let formatted = e.format(&opts);
+            prop_assert_eq!(parse_expr(&formatted).ok(), Some(e));
+        }
+    }
+
+    #[test]
+    fn quoting() {
+        let e = parse_expr("λ(`Natural` : Type) → { Natural = `if`.`a b` }")
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "λ(`Natural` : Type) → { Natural = `if`.`a b` }"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Error-recovering parsing, for editors and other tools that want to report
+//! all the syntax errors in a file at once.
+//!
+//! pest stops at the first error, so recovery works by repairing the input:
+//! at each error we try a few small edits (inserting a missing keyword or
+//! delimiter, inserting a placeholder expression, or skipping the offending
+//! text), keep the one that lets parsing get the furthest, and parse again.
+//! Placeholders become [Recovered::Error] nodes in the resulting expression.
+//!
+//! Each attempt reparses the whole input, so the total amount of text parsed
+//! is bounded in proportion to the size of the input. Past that, recovery
+//! gives up and only the errors found so far are reported.
+use std::fmt::{self, Display};
+
+use crate::stack::ensure_sufficient_stack;
+use crate::sync::Rc;
+use crate::*;
+
+/// Gives up after that many errors.
+const MAX_ERRORS: usize = 100;
+const MAX_REPAIRS: usize = 4 * MAX_ERRORS;
+/// Gives up after parsing that many bytes per byte of input, in total...
+const WORK_PER_BYTE: usize = 64;
+/// ...unless the input is small.
+const MIN_WORK: usize = 1 << 20;
+
+/// Text that may be inserted to repair the input, in order of preference.
+const INSERTIONS: &[&str] = &[
+    " in ", " then ", " else ", " → ", " = ", " : ", ", ", ")", "}", "]", ">",
+];
+
+/// What is embedded in an expression parsed with [parse_expr_recovering].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recovered {
+    Import(Import),
+    /// Stands for a part of the input that could not be parsed. Contains the
+    /// index of the corresponding error.
+    Error(usize),
+}
+
+impl Display for Recovered {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Recovered::Import(i) => i.fmt(f),
+            Recovered::Error(_) => f.write_str("<error>"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PartialParse {
+    /// The parsed expression, with error nodes where the input had to be
+    /// repaired. `None` if the input could not be repaired.
+    pub expr: Option<SubExpr<Span, Recovered>>,
+    /// The syntax errors, in order of position in the input.
+    pub errors: Vec<ParseError>,
+}
+
+/// Parses an expression, recovering from syntax errors. Unlike [parse_expr],
+/// this reports all the errors instead of only the first one.
+pub fn parse_expr_recovering(s: &str) -> PartialParse {
+    let mut marker = String::from("!error");
+    while s.contains(&marker) {
+        marker.insert(0, '!');
+    }
+    let mut errors = Vec::new();
+    let mut last_error_pos = None;
+    let mut patched = Patched::new(s);
+    let mut work = Work::new(s);
+    for _ in 0..MAX_REPAIRS {
+        let err = match work.parse(&patched.text) {
+            Ok(e) => {
+                let input: Rc<str> = s.to_string().into();
+                let expr = rebuild(&e, &marker, &input, &patched.origin);
+                return PartialParse {
+                    expr: Some(expr),
+                    errors,
+                };
+            }
+            Err(err) => err,
+        };
+        let pos = err.offset();
+        let orig_pos = patched.origin[pos];
+        // A repair may take several steps, e.g. inserting `in` and then a
+        // placeholder for the body. Only the first error gets reported.
+        if last_error_pos != Some(orig_pos) {
+            if errors.len() == MAX_ERRORS {
+                break;
+            }
+            errors.push(err.moved_to(s, orig_pos));
+            last_error_pos = Some(orig_pos);
+        }
+
+        let placeholder = format!(" `{}{}` ", marker, errors.len() - 1);
+        let mut best: Option<(usize, Patched)> = None;
+        for (start, end, insert) in repairs(&patched.text, pos, &placeholder) {
+            if work.is_exhausted() {
+                return PartialParse { expr: None, errors };
+            }
+            let candidate = patched.edit(start, end, &insert);
+            let progress = match work.parse(&candidate.text) {
+                Ok(_) => std::usize::MAX,
+                // The inserted text must at least be accepted by the parser
+                Err(err) if err.offset() >= start + insert.len() => {
+                    candidate.origin[err.offset()]
+                }
+                Err(_) => continue,
+            };
+            let is_better = match &best {
+                Some((best_progress, _)) => progress > *best_progress,
+                None => progress >= orig_pos,
+            };
+            if is_better {
+                best = Some((progress, candidate));
+                if progress == std::usize::MAX {
+                    break;
+                }
+            }
+        }
+        match best {
+            Some((_, p)) => patched = p,
+            None => break,
+        }
+    }
+    PartialParse { expr: None, errors }
+}
+
+/// Counts the bytes parsed so far.
+struct Work {
+    done: usize,
+    budget: usize,
+}
+
+impl Work {
+    fn new(s: &str) -> Self {
+        Work {
+            done: 0,
+            budget: std::cmp::max(MIN_WORK, WORK_PER_BYTE * s.len()),
+        }
+    }
+
+    fn parse(&mut self, s: &str) -> ParseResult<SubExpr<Span, Import>> {
+        self.done += s.len();
+        parse_expr(s)
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.done >= self.budget
+    }
+}
+
+/// The input after some repairs, along with the offset in the original input
+/// that each offset comes from.
+#[derive(Debug, Clone)]
+struct Patched {
+    text: String,
+    origin: Vec<usize>,
+}
+
+impl Patched {
+    fn new(s: &str) -> Self {
+        Patched {
+            text: s.to_owned(),
+            origin: (0..=s.len()).collect(),
+        }
+    }
+
+    /// Replaces the text between `start` and `end` with `insert`.
+    fn edit(&self, start: usize, end: usize, insert: &str) -> Self {
+        let text = [&self.text[..start], insert, &self.text[end..]].concat();
+        let mut origin = self.origin[..start].to_vec();
+        origin.extend(std::iter::repeat(self.origin[start]).take(insert.len()));
+        origin.extend_from_slice(&self.origin[end..]);
+        Patched { text, origin }
+    }
+}
+
+/// The edits to try at an error at `pos`, as `(start, end, replacement)`.
+fn repairs(
+    s: &str,
+    pos: usize,
+    placeholder: &str,
+) -> Vec<(usize, usize, String)> {
+    let mut repairs: Vec<_> = INSERTIONS
+        .iter()
+        .map(|i| (pos, pos, i.to_string()))
+        .collect();
+    repairs.push((pos, pos, placeholder.to_owned()));
+    let rest = &s[pos..];
+    // Skip the offending token
+    let token_end = rest
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(s.len(), |(i, _)| pos + i);
+    repairs.push((pos, token_end, " ".to_owned()));
+    // Skip to the end of the line or of the enclosing item, replacing the
+    // text with a placeholder
+    let mut depth = 0;
+    let mut sync = s.len();
+    for (i, c) in rest.char_indices() {
+        match c {
+            '(' | '{' | '[' | '<' => depth += 1,
+            ')' | '}' | ']' | '>' if depth > 0 => depth -= 1,
+            ')' | '}' | ']' | '>' | ',' | '|' if depth == 0 => {
+                sync = pos + i;
+                break;
+            }
+            '\n' if depth == 0 => {
+                sync = pos + i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    if sync > pos {
+        repairs.push((pos, sync, placeholder.to_owned()));
+    }
+    repairs
+}
+
+/// Turns the expression parsed from the repaired input into one over the
+/// original input.
+fn rebuild(
+    e: &SubExpr<Span, Import>,
+    marker: &str,
+    input: &Rc<str>,
+    origin: &[usize],
+) -> SubExpr<Span, Recovered> {
+    ensure_sufficient_stack(|| {
+        let x = match e.as_ref() {
+            ExprF::Var(V(l, _)) if String::from(l).starts_with(marker) => {
+                let index = String::from(l)[marker.len()..].parse().unwrap();
+                ExprF::Embed(Recovered::Error(index))
+            }
+            x => x.map_ref(
+                |e| rebuild(e, marker, input, origin),
+                |i| Recovered::Import(i.clone()),
+            ),
+        };
+        match e.note() {
+            Some(span) => SubExpr::new(x, span.remap(input, |i| origin[i])),
+            None => SubExpr::from_expr_no_note(x),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_all_errors() {
+        let p = parse_expr_recovering("{ a = , b = 2, c = }");
+        assert_eq!(p.errors.len(), 2);
+        assert_eq!(
+            p.expr.unwrap().to_string(),
+            "{ a = <error>, b = 2, c = <error> }"
+        );
+    }
+
+    #[test]
+    fn missing_in() {
+        let p = parse_expr_recovering("let x = 1 x");
+        assert_eq!(p.errors.len(), 1);
+        assert!(p.errors[0].to_string().contains("expected `in`"));
+        assert_eq!(p.expr.unwrap().to_string(), "let x = 1 x in <error>");
+    }
+
+    #[test]
+    fn large_inputs_are_given_up_on() {
+        let fields: Vec<_> = (0..5000)
+            .map(|i| {
+                if i % 10 == 0 {
+                    format!("f{} = ", i)
+                } else {
+                    format!("f{} = {}", i, i)
+                }
+            })
+            .collect();
+        let p = parse_expr_recovering(&format!("{{ {} }}", fields.join(", ")));
+        assert!(p.expr.is_none());
+        assert!(!p.errors.is_empty() && p.errors.len() < MAX_ERRORS);
+    }
+
+    #[test]
+    fn no_errors() {
+        let p = parse_expr_recovering("[1, 2]");
+        assert!(p.errors.is_empty());
+        assert!(p.expr.is_some());
+    }
+}
@@ -0,0 +1,19 @@
+//! Protection against stack overflows on deeply nested expressions.
+//!
+//! Machine-generated configs can nest expressions hundreds of thousands of
+//! levels deep, e.g. a long chain of `#` or of `let`s. Every recursive
+//! traversal of expressions goes through [ensure_sufficient_stack], which
+//! moves the computation to a freshly allocated stack segment when the
+//! current one is about to run out.
+
+/// If less than this much stack remains, allocate a new segment.
+const RED_ZONE: usize = 64 * 1024;
+/// The size of newly allocated stack segments.
+const STACK_PER_RECURSION: usize = 1024 * 1024;
+
+/// Runs `f`, growing the stack beforehand if necessary. Wrap the body of
+/// recursive functions in this.
+#[inline]
+pub fn ensure_sufficient_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, STACK_PER_RECURSION, f)
+}
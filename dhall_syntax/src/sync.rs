@@ -0,0 +1,11 @@
+//! The reference-counted pointer (and its weak counterpart) shared by the syntax tree and everything
+//! built on top of it.
+//!
+//! This is `std::rc::Rc` by default. With the `sync` feature it becomes
+//! `std::sync::Arc`, which makes expressions `Send + Sync` at the cost of
+//! atomic reference counting.
+
+#[cfg(not(feature = "sync"))]
+pub use std::rc::{Rc, Weak};
+#[cfg(feature = "sync")]
+pub use std::sync::{Arc as Rc, Weak};
@@ -0,0 +1,9 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use dhall::pipeline::Parsed;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Parsed::parse_binary(data);
+});
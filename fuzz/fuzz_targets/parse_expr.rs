@@ -0,0 +1,9 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let _ = dhall_syntax::parse_expr(s);
+    }
+});
@@ -0,0 +1,26 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use dhall::de::EvalLimits;
+use dhall::pipeline::Parsed;
+
+// Imports are not resolved, so that inputs don't touch the filesystem or the
+// network. The limits keep expressions that blow up from stalling the fuzzer.
+fuzz_target!(|data: &[u8]| {
+    let s = match std::str::from_utf8(data) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+    let limits = EvalLimits {
+        max_steps: Some(100_000),
+        max_list_length: Some(10_000),
+        max_text_length: Some(100_000),
+        max_depth: Some(1_000),
+    };
+    let _ = limits.run(|| {
+        let typed = Parsed::parse_str(s)?.skip_resolve()?.typecheck()?;
+        // Normalization is lazy; reading back the result forces it
+        Ok(typed.normalize().to_expr())
+    });
+});
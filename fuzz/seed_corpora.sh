@@ -0,0 +1,22 @@
+#!/bin/sh
+# Fills the fuzzing corpora with the test files from dhall-lang. Run it once
+# before `cargo fuzz run <target>`.
+set -e
+cd "$(dirname "$0")"
+tests=../dhall-lang/tests
+
+seed() {
+    target=$1
+    shift
+    mkdir -p "corpus/$target"
+    find "$@" -type f -name "$pattern" | while read -r f; do
+        cp "$f" "corpus/$target/$(echo "${f#$tests/}" | tr / _)"
+    done
+}
+
+pattern='*.dhall'
+seed parse_expr "$tests"
+seed typecheck "$tests/typecheck" "$tests/type-inference" \
+    "$tests/normalization" "$tests/alpha-normalization"
+pattern='*.dhallb'
+seed decode "$tests"